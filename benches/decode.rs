@@ -0,0 +1,69 @@
+//! Compares decoding a large byte-string payload from a borrowed UR, which
+//! must copy the shared bytes, with decoding from an owned UR, which can move
+//! them.
+//!
+//! Run with `cargo bench --bench decode`.
+
+use std::time::{Duration, Instant};
+
+use bc_ur::prelude::*;
+
+const PAYLOAD_LEN: usize = 8 * 1024 * 1024;
+const ITERATIONS: u32 = 20;
+
+struct Blob(Vec<u8>);
+
+impl CBORTagged for Blob {
+    fn cbor_tags() -> Vec<Tag> { vec![Tag::new(90300, "blob")] }
+}
+
+impl From<Blob> for CBOR {
+    fn from(value: Blob) -> Self { value.tagged_cbor() }
+}
+
+impl CBORTaggedEncodable for Blob {
+    fn untagged_cbor(&self) -> CBOR { CBOR::to_byte_string(&self.0) }
+}
+
+impl TryFrom<CBOR> for Blob {
+    type Error = dcbor::Error;
+
+    fn try_from(cbor: CBOR) -> dcbor::Result<Self> {
+        Self::from_tagged_cbor(cbor)
+    }
+}
+
+impl CBORTaggedDecodable for Blob {
+    fn from_untagged_cbor(cbor: CBOR) -> dcbor::Result<Self> {
+        Ok(Self(cbor.try_into_byte_string()?))
+    }
+}
+
+fn make_ur() -> UR { Blob(vec![0xa5; PAYLOAD_LEN]).ur() }
+
+fn time(label: &str, mut f: impl FnMut() -> Duration) {
+    let total: Duration = (0..ITERATIONS).map(|_| f()).sum();
+    println!("{label:<24} {:>10.3?} per iteration", total / ITERATIONS);
+}
+
+fn main() {
+    println!("payload: {} bytes, {} iterations", PAYLOAD_LEN, ITERATIONS);
+
+    time("from_ur (borrowed)", || {
+        let ur = make_ur();
+        let start = Instant::now();
+        let blob = Blob::from_ur(&ur).unwrap();
+        let elapsed = start.elapsed();
+        assert_eq!(blob.0.len(), PAYLOAD_LEN);
+        elapsed
+    });
+
+    time("from_ur_owned", || {
+        let ur = make_ur();
+        let start = Instant::now();
+        let blob = Blob::from_ur_owned(ur).unwrap();
+        let elapsed = start.elapsed();
+        assert_eq!(blob.0.len(), PAYLOAD_LEN);
+        elapsed
+    });
+}
@@ -0,0 +1,17 @@
+#![no_main]
+
+use bc_ur::bytewords::{self, Style};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    for style in [Style::Standard, Style::Uri, Style::Minimal] {
+        if let Ok(data) = bytewords::decode(input, style) {
+            // Anything accepted must survive a round trip.
+            let encoded = bytewords::encode(&data, style);
+            assert_eq!(bytewords::decode(&encoded, style).unwrap(), data);
+        }
+    }
+    for token in input.split_whitespace() {
+        let _ = bytewords::canonicalize_byteword(token);
+    }
+});
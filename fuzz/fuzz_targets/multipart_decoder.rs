@@ -0,0 +1,13 @@
+#![no_main]
+
+use bc_ur::prelude::*;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    // Each line is one scanned part.
+    let mut decoder = MultipartDecoder::new();
+    for line in input.lines() {
+        let _ = decoder.receive(line);
+    }
+    let _ = decoder.message();
+});
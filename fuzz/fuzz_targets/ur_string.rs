@@ -0,0 +1,13 @@
+#![no_main]
+
+use bc_ur::{DecodeMode, prelude::*};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    if let Ok(ur) = UR::from_ur_string(input) {
+        // Anything accepted must survive a round trip.
+        assert_eq!(UR::from_ur_string(ur.string()).unwrap(), ur);
+    }
+    let _ = UR::from_ur_string_with_mode(input, DecodeMode::Permissive);
+    let _ = RawUR::from_ur_string(input).map(|raw| raw.to_ur());
+});
@@ -1,7 +1,9 @@
+import_stdlib!();
+
 pub use bytewords::Style;
 pub use ur::bytewords;
 
-use crate::Result;
+use crate::{Case, Result};
 
 pub fn encode(data: impl AsRef<[u8]>, style: Style) -> String {
     ur::bytewords::encode(data.as_ref(), style)
@@ -52,6 +54,36 @@ pub fn bytemoji_identifier(data: &[u8; 4]) -> String {
     encode_to_bytemojis(data)
 }
 
+/// Returns the CRC-32 (ISO-HDLC) checksum used by bytewords and the fountain
+/// codes.
+#[must_use]
+pub fn crc32(data: &[u8]) -> u32 {
+    const CRC32: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+    CRC32.checksum(data)
+}
+
+/// Writes `data` followed by its CRC-32 checksum as minimal bytewords,
+/// without allocating.
+pub fn write_minimal(
+    w: &mut impl fmt::Write,
+    data: &[u8],
+    case: Case,
+) -> fmt::Result {
+    let checksum = crc32(data).to_be_bytes();
+    for &b in data.iter().chain(checksum.iter()) {
+        let word = BYTEWORDS[b as usize].as_bytes();
+        let (first, last) = (word[0], word[word.len() - 1]);
+        if case == Case::Upper {
+            w.write_char(first.to_ascii_uppercase() as char)?;
+            w.write_char(last.to_ascii_uppercase() as char)?;
+        } else {
+            w.write_char(first as char)?;
+            w.write_char(last as char)?;
+        }
+    }
+    Ok(())
+}
+
 pub fn decode(data: &str, style: Style) -> Result<Vec<u8>> {
     Ok(ur::bytewords::decode(data, style)?)
 }
@@ -65,32 +97,27 @@ pub fn is_valid_bytemoji(emoji: &str) -> bool { BYTEMOJIS.contains(&emoji) }
 /// byteword or any of its short forms.
 #[must_use]
 pub fn canonicalize_byteword(token: &str) -> Option<String> {
-    use std::sync::LazyLock;
-
-    static WORD_SET: LazyLock<std::collections::HashSet<&'static str>> =
+    static WORD_SET: LazyLock<HashSet<&'static str>> =
         LazyLock::new(|| BYTEWORDS.iter().copied().collect());
-    static FIRST_LAST: LazyLock<
-        std::collections::HashMap<String, &'static str>,
-    > = LazyLock::new(|| {
-        BYTEWORDS
-            .iter()
-            .map(|w| {
-                let bytes = w.as_bytes();
-                let key = format!(
-                    "{}{}",
-                    bytes[0] as char,
-                    bytes[bytes.len() - 1] as char
-                );
-                (key, *w)
-            })
-            .collect()
-    });
-    static FIRST_THREE: LazyLock<
-        std::collections::HashMap<&'static str, &'static str>,
-    > = LazyLock::new(|| BYTEWORDS.iter().map(|w| (&w[..3], *w)).collect());
-    static LAST_THREE: LazyLock<
-        std::collections::HashMap<&'static str, &'static str>,
-    > = LazyLock::new(|| BYTEWORDS.iter().map(|w| (&w[1..], *w)).collect());
+    static FIRST_LAST: LazyLock<HashMap<String, &'static str>> =
+        LazyLock::new(|| {
+            BYTEWORDS
+                .iter()
+                .map(|w| {
+                    let bytes = w.as_bytes();
+                    let key = format!(
+                        "{}{}",
+                        bytes[0] as char,
+                        bytes[bytes.len() - 1] as char
+                    );
+                    (key, *w)
+                })
+                .collect()
+        });
+    static FIRST_THREE: LazyLock<HashMap<&'static str, &'static str>> =
+        LazyLock::new(|| BYTEWORDS.iter().map(|w| (&w[..3], *w)).collect());
+    static LAST_THREE: LazyLock<HashMap<&'static str, &'static str>> =
+        LazyLock::new(|| BYTEWORDS.iter().map(|w| (&w[1..], *w)).collect());
 
     let lower = token.to_ascii_lowercase();
     match lower.len() {
@@ -175,7 +202,7 @@ mod tests {
     #[test]
     fn test_bytemoji_uniqueness() {
         let bytemojis = BYTEMOJIS.to_vec();
-        let mut dict = std::collections::HashMap::new();
+        let mut dict = HashMap::new();
         for bytemoji in bytemojis.iter() {
             let count = dict.entry(bytemoji).or_insert(0);
             *count += 1;
@@ -233,7 +260,7 @@ mod tests {
         let encoded = encode_to_words(&all_bytes);
         let words: Vec<&str> = encoded.split(' ').collect();
         assert_eq!(words.len(), 256);
-        let unique: std::collections::HashSet<&&str> = words.iter().collect();
+        let unique: HashSet<&&str> = words.iter().collect();
         assert_eq!(
             unique.len(),
             256,
@@ -250,6 +277,17 @@ mod tests {
         assert_eq!(emojis.len(), 8);
     }
 
+    #[test]
+    fn test_write_minimal_matches_encode() {
+        let data = [0u8, 1, 2, 128, 255];
+        let mut lower = String::new();
+        write_minimal(&mut lower, &data, Case::Lower).unwrap();
+        assert_eq!(lower, encode(data, Style::Minimal));
+        let mut upper = String::new();
+        write_minimal(&mut upper, &data, Case::Upper).unwrap();
+        assert_eq!(upper, lower.to_uppercase());
+    }
+
     #[test]
     fn test_encode_to_minimal_bytewords() {
         // "able" → "ae", "acid" → "ad", "also" → "ao", "apex" → "ax"
@@ -0,0 +1,526 @@
+import_stdlib!();
+
+use dcbor::prelude::*;
+
+use crate::{Error, Result};
+
+/// How strictly UR payloads are checked for conformance to dCBOR.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DecodeMode {
+    /// Reject any payload that is not deterministically encoded, reporting
+    /// the first problem as [`Error::NonConformant`].
+    #[default]
+    Strict,
+    /// Accept well-formed but non-deterministic payloads, converting them to
+    /// their canonical form and reporting every problem found.
+    Permissive,
+}
+
+/// The ways in which well-formed CBOR can fail to be valid dCBOR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonconformanceKind {
+    /// An integer, length, or tag was not encoded in its shortest form.
+    NonCanonicalInteger,
+    /// A floating point value was not encoded in its shortest form, could
+    /// have been encoded as an integer, or was a non-canonical NaN.
+    NonCanonicalFloat,
+    /// An indefinite-length string, array, or map was used.
+    IndefiniteLength,
+    /// A text string was not in Unicode Normalization Form C.
+    NonCanonicalString,
+    /// A simple value other than `false`, `true`, `null`, or a float was
+    /// used. Permissive decoding replaces it with `null`.
+    InvalidSimpleValue,
+    /// Map keys were not in ascending lexicographic order of their encoding.
+    MisorderedMapKey,
+    /// A map key occurred more than once. Permissive decoding keeps the last
+    /// value.
+    DuplicateMapKey,
+    /// Bytes followed the CBOR item. Permissive decoding ignores them.
+    UnusedData,
+}
+
+impl fmt::Display for NonconformanceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::NonCanonicalInteger => "non-canonical integer encoding",
+            Self::NonCanonicalFloat => "non-canonical float encoding",
+            Self::IndefiniteLength => "indefinite-length item",
+            Self::NonCanonicalString => "string not in Normalization Form C",
+            Self::InvalidSimpleValue => "invalid simple value",
+            Self::MisorderedMapKey => "misordered map key",
+            Self::DuplicateMapKey => "duplicate map key",
+            Self::UnusedData => "unused data after CBOR item",
+        };
+        f.write_str(s)
+    }
+}
+
+/// A single departure from dCBOR found in a CBOR payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Nonconformance {
+    offset: usize,
+    kind: NonconformanceKind,
+}
+
+impl Nonconformance {
+    /// Returns the byte offset within the CBOR payload of the offending item.
+    pub fn offset(&self) -> usize { self.offset }
+
+    pub fn kind(&self) -> NonconformanceKind { self.kind }
+}
+
+impl fmt::Display for Nonconformance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at CBOR byte {}", self.kind, self.offset)
+    }
+}
+
+/// The deepest nesting of arrays, maps and tags accepted in a payload. CBOR
+/// is decoded recursively, so deeper payloads could exhaust the stack.
+pub(crate) const MAX_NESTING_DEPTH: usize = 256;
+
+/// Decodes a CBOR payload according to `mode`, returning the canonical value
+/// and, in permissive mode, the problems that were corrected.
+pub(crate) fn decode_cbor(
+    data: &[u8],
+    mode: DecodeMode,
+) -> Result<(CBOR, Vec<Nonconformance>)> {
+    check_nesting(data)?;
+    let strict_err = match CBOR::try_from_data(data) {
+        Ok(cbor) => return Ok((cbor, Vec::new())),
+        Err(err) => err,
+    };
+    let mut scanner = Scanner { data, pos: 0, found: Vec::new() };
+    let cbor = match scanner.item() {
+        Ok(cbor) => cbor,
+        // Malformed rather than merely non-deterministic.
+        Err(_) => return Err(strict_err.into()),
+    };
+    if scanner.pos < data.len() {
+        scanner.report(scanner.pos, NonconformanceKind::UnusedData);
+    }
+    match mode {
+        DecodeMode::Permissive => Ok((cbor, scanner.found)),
+        DecodeMode::Strict => match scanner.found.first() {
+            Some(&first) => Err(Error::NonConformant(first)),
+            None => Err(strict_err.into()),
+        },
+    }
+}
+
+/// Rejects a payload whose first item nests more deeply than
+/// [`MAX_NESTING_DEPTH`], reading only the item headers. Malformed payloads
+/// that are shallow enough are left for the decoder to reject.
+pub(crate) fn check_nesting(data: &[u8]) -> Result<()> {
+    // The items remaining in each open container, or `None` if it has
+    // indefinite length.
+    let mut open: Vec<Option<u64>> = Vec::new();
+    let mut pos = 0;
+    loop {
+        let Some(&header) = data.get(pos) else {
+            return Ok(());
+        };
+        pos += 1;
+        if header == 0xff && open.last() == Some(&None) {
+            open.pop();
+        } else {
+            if let Some(Some(remaining)) = open.last_mut() {
+                *remaining -= 1;
+            }
+            let (major, info) = (header >> 5, header & 0x1f);
+            let argument = match info {
+                0..=23 => Some(info as u64),
+                24..=27 => {
+                    let len = 1 << (info - 24);
+                    let Some(bytes) = data.get(pos..pos + len) else {
+                        return Ok(());
+                    };
+                    pos += len;
+                    Some(be_u64(bytes))
+                }
+                31 => None,
+                _ => return Ok(()),
+            };
+            match (major, argument) {
+                (2 | 3, Some(len)) => {
+                    let len = usize::try_from(len).unwrap_or(usize::MAX);
+                    pos = pos.saturating_add(len);
+                }
+                (2..=5, None) => open.push(None),
+                (4, Some(len)) => open.push(Some(len)),
+                (5, Some(len)) => open.push(Some(len.saturating_mul(2))),
+                (6, _) => open.push(Some(1)),
+                _ => {}
+            }
+            if open.len() > MAX_NESTING_DEPTH {
+                return Err(Error::NestingTooDeep(MAX_NESTING_DEPTH));
+            }
+        }
+        while open.last() == Some(&Some(0)) {
+            open.pop();
+        }
+        if open.is_empty() {
+            return Ok(());
+        }
+    }
+}
+
+/// A lenient CBOR parser that accepts any well-formed encoding and records
+/// where it departs from dCBOR.
+struct Scanner<'a> {
+    data: &'a [u8],
+    pos: usize,
+    found: Vec<Nonconformance>,
+}
+
+enum Length {
+    Definite(u64),
+    Indefinite,
+}
+
+impl Scanner<'_> {
+    fn report(&mut self, offset: usize, kind: NonconformanceKind) {
+        self.found.push(Nonconformance { offset, kind });
+    }
+
+    fn bytes(&mut self, len: u64) -> dcbor::Result<&[u8]> {
+        let len = usize::try_from(len).map_err(|_| dcbor::Error::Underrun)?;
+        if self.data.len() - self.pos < len {
+            return Err(dcbor::Error::Underrun);
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> dcbor::Result<u8> { Ok(self.bytes(1)?[0]) }
+
+    /// Reads the argument of a header, reporting it if not in shortest form.
+    fn argument(&mut self, start: usize, info: u8) -> dcbor::Result<Length> {
+        let (value, min) = match info {
+            0..=23 => return Ok(Length::Definite(info as u64)),
+            24 => (self.byte()? as u64, 24),
+            25 => (be_u64(self.bytes(2)?), 0x100),
+            26 => (be_u64(self.bytes(4)?), 0x1_0000),
+            27 => (be_u64(self.bytes(8)?), 0x1_0000_0000),
+            31 => return Ok(Length::Indefinite),
+            _ => {
+                return Err(dcbor::Error::UnsupportedHeaderValue(
+                    self.data[start],
+                ));
+            }
+        };
+        if value < min {
+            self.report(start, NonconformanceKind::NonCanonicalInteger);
+        }
+        Ok(Length::Definite(value))
+    }
+
+    fn definite(&mut self, start: usize, info: u8) -> dcbor::Result<u64> {
+        match self.argument(start, info)? {
+            Length::Definite(value) => Ok(value),
+            Length::Indefinite => {
+                Err(dcbor::Error::UnsupportedHeaderValue(self.data[start]))
+            }
+        }
+    }
+
+    fn at_break(&mut self) -> dcbor::Result<bool> {
+        if *self.data.get(self.pos).ok_or(dcbor::Error::Underrun)? == 0xff {
+            self.pos += 1;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Reads the contents of a byte or text string, concatenating chunks of
+    /// an indefinite-length string.
+    fn string(
+        &mut self,
+        start: usize,
+        major: u8,
+        info: u8,
+    ) -> dcbor::Result<Vec<u8>> {
+        match self.argument(start, info)? {
+            Length::Definite(len) => Ok(self.bytes(len)?.to_vec()),
+            Length::Indefinite => {
+                self.report(start, NonconformanceKind::IndefiniteLength);
+                let mut content = Vec::new();
+                while !self.at_break()? {
+                    let chunk_start = self.pos;
+                    let header = self.byte()?;
+                    if header >> 5 != major {
+                        return Err(dcbor::Error::UnsupportedHeaderValue(
+                            header,
+                        ));
+                    }
+                    let len = self.definite(chunk_start, header & 0x1f)?;
+                    content.extend_from_slice(self.bytes(len)?);
+                }
+                Ok(content)
+            }
+        }
+    }
+
+    fn item(&mut self) -> dcbor::Result<CBOR> {
+        let start = self.pos;
+        let header = self.byte()?;
+        let (major, info) = (header >> 5, header & 0x1f);
+        match major {
+            0 => Ok(self.definite(start, info)?.into()),
+            1 => Ok(CBORCase::Negative(self.definite(start, info)?).into()),
+            2 => Ok(CBOR::to_byte_string(self.string(start, major, info)?)),
+            3 => {
+                let bytes = self.string(start, major, info)?;
+                let text = String::from_utf8(bytes)
+                    .map_err(|e| dcbor::Error::InvalidString(e.utf8_error()))?;
+                // dCBOR normalizes text to NFC when encoding, so a round trip
+                // reveals whether the original was normalized.
+                let normalized = CBOR::try_from_data(
+                    CBOR::from(text.as_str()).to_cbor_data(),
+                )?;
+                if !matches!(normalized.as_case(), CBORCase::Text(s) if *s == text)
+                {
+                    self.report(start, NonconformanceKind::NonCanonicalString);
+                }
+                Ok(normalized)
+            }
+            4 => {
+                let mut items = Vec::new();
+                match self.argument(start, info)? {
+                    Length::Definite(len) => {
+                        for _ in 0..len {
+                            items.push(self.item()?);
+                        }
+                    }
+                    Length::Indefinite => {
+                        self.report(
+                            start,
+                            NonconformanceKind::IndefiniteLength,
+                        );
+                        while !self.at_break()? {
+                            items.push(self.item()?);
+                        }
+                    }
+                }
+                Ok(items.into())
+            }
+            5 => {
+                let mut map = Map::new();
+                let mut previous_key: Option<Vec<u8>> = None;
+                let len = match self.argument(start, info)? {
+                    Length::Definite(len) => Some(len),
+                    Length::Indefinite => {
+                        self.report(
+                            start,
+                            NonconformanceKind::IndefiniteLength,
+                        );
+                        None
+                    }
+                };
+                let mut count = 0;
+                loop {
+                    match len {
+                        Some(len) if count == len => break,
+                        None if self.at_break()? => break,
+                        _ => {}
+                    }
+                    let key_start = self.pos;
+                    let key = self.item()?;
+                    let value = self.item()?;
+                    let key_data = key.to_cbor_data();
+                    if map.contains_key(key.clone()) {
+                        self.report(
+                            key_start,
+                            NonconformanceKind::DuplicateMapKey,
+                        );
+                    } else if previous_key
+                        .as_ref()
+                        .is_some_and(|p| key_data < *p)
+                    {
+                        self.report(
+                            key_start,
+                            NonconformanceKind::MisorderedMapKey,
+                        );
+                    }
+                    map.insert(key, value);
+                    previous_key = Some(key_data);
+                    count += 1;
+                }
+                Ok(map.into())
+            }
+            6 => {
+                let tag = self.definite(start, info)?;
+                let item = self.item()?;
+                Ok(CBOR::to_tagged_value(tag, item))
+            }
+            _ => self.simple(start, info),
+        }
+    }
+
+    fn simple(&mut self, start: usize, info: u8) -> dcbor::Result<CBOR> {
+        let value = match info {
+            20 => return Ok(false.into()),
+            21 => return Ok(true.into()),
+            22 => return Ok(CBOR::null()),
+            25 => f16_to_f64(be_u64(self.bytes(2)?) as u16),
+            26 => f32::from_bits(be_u64(self.bytes(4)?) as u32) as f64,
+            27 => f64::from_bits(be_u64(self.bytes(8)?)),
+            // Reserved additional information, and two-byte simple values
+            // below 32, are not well-formed (RFC 8949 §3.3).
+            28..=31 => {
+                return Err(dcbor::Error::UnsupportedHeaderValue(
+                    self.data[start],
+                ));
+            }
+            24 if self.byte()? < 32 => {
+                return Err(dcbor::Error::UnsupportedHeaderValue(
+                    self.data[start],
+                ));
+            }
+            _ => {
+                self.report(start, NonconformanceKind::InvalidSimpleValue);
+                return Ok(CBOR::null());
+            }
+        };
+        let cbor = CBOR::from(value);
+        if cbor.to_cbor_data() != self.data[start..self.pos] {
+            self.report(start, NonconformanceKind::NonCanonicalFloat);
+        }
+        Ok(cbor)
+    }
+}
+
+fn be_u64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |acc, &b| (acc << 8) | b as u64)
+}
+
+fn f16_to_f64(bits: u16) -> f64 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as u64;
+    let fraction = (bits & 0x3ff) as u64;
+    // Built from bits rather than with `powi`, which needs `std`.
+    let magnitude = match exponent {
+        0 => fraction as f64 * f64::from_bits((1023 - 24) << 52),
+        0x1f if fraction == 0 => f64::INFINITY,
+        0x1f => f64::NAN,
+        _ => f64::from_bits(((exponent + 1023 - 15) << 52) | (fraction << 42)),
+    };
+    sign * magnitude
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn permissive(hex: &str) -> (CBOR, Vec<NonconformanceKind>) {
+        let data = hex::decode(hex).unwrap();
+        let (cbor, found) = decode_cbor(&data, DecodeMode::Permissive).unwrap();
+        (cbor, found.iter().map(|n| n.kind()).collect())
+    }
+
+    #[test]
+    fn test_conformant() {
+        let (cbor, found) = permissive("83010203");
+        assert_eq!(cbor, CBOR::from(vec![1, 2, 3]));
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn test_nonconformances() {
+        use NonconformanceKind::*;
+        // 1 encoded in two bytes
+        assert_eq!(permissive("1801"), (1.into(), vec![NonCanonicalInteger]));
+        // 1.0 encoded as a half float
+        assert_eq!(permissive("f93c00"), (1.into(), vec![NonCanonicalFloat]));
+        // 1.5 encoded as a double
+        assert_eq!(
+            permissive("fb3ff8000000000000"),
+            (1.5.into(), vec![NonCanonicalFloat])
+        );
+        // indefinite-length array [1, 2]
+        assert_eq!(
+            permissive("9f0102ff"),
+            (vec![1, 2].into(), vec![IndefiniteLength])
+        );
+        // undefined
+        assert_eq!(permissive("f7"), (CBOR::null(), vec![InvalidSimpleValue]));
+        // {2: 0, 1: 0}
+        assert_eq!(permissive("a202000100").1, vec![MisorderedMapKey]);
+        // {1: 0, 1: 1}
+        assert_eq!(permissive("a201000101").1, vec![DuplicateMapKey]);
+        // "é" as e + combining acute accent
+        let (cbor, found) = permissive("6365cc81");
+        assert_eq!(cbor, CBOR::from("\u{e9}"));
+        assert_eq!(found, vec![NonCanonicalString]);
+        // trailing byte
+        assert_eq!(permissive("0100"), (1.into(), vec![UnusedData]));
+    }
+
+    #[test]
+    fn test_f16_to_f64() {
+        assert_eq!(f16_to_f64(0x3c00), 1.0);
+        assert_eq!(f16_to_f64(0xc000), -2.0);
+        assert_eq!(f16_to_f64(0x3e00), 1.5);
+        assert_eq!(f16_to_f64(0x7bff), 65504.0);
+        assert_eq!(f16_to_f64(0x0001), 5.960464477539063e-8);
+        assert_eq!(f16_to_f64(0x03ff), 6.097555160522461e-5);
+        assert_eq!(f16_to_f64(0x7c00), f64::INFINITY);
+        assert!(f16_to_f64(0x7e00).is_nan());
+    }
+
+    #[test]
+    fn test_strict() {
+        let data = hex::decode("8301021803").unwrap();
+        match decode_cbor(&data, DecodeMode::Strict) {
+            Err(Error::NonConformant(n)) => {
+                assert_eq!(n.kind(), NonconformanceKind::NonCanonicalInteger);
+                assert_eq!(n.offset(), 3);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        // Truncated input is malformed, not merely non-conformant.
+        let data = hex::decode("8301").unwrap();
+        assert!(matches!(
+            decode_cbor(&data, DecodeMode::Strict),
+            Err(Error::Cbor(dcbor::Error::Underrun))
+        ));
+        // So are reserved simple value encodings, in either mode.
+        for hex in ["fc", "fd", "fe", "f81f"] {
+            let data = hex::decode(hex).unwrap();
+            for mode in [DecodeMode::Strict, DecodeMode::Permissive] {
+                assert!(matches!(
+                    decode_cbor(&data, mode),
+                    Err(Error::Cbor(_))
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn test_nesting_too_deep() {
+        let nested = |depth| {
+            let mut data = vec![0x81; depth];
+            data.push(0x00);
+            data
+        };
+        let data = nested(MAX_NESTING_DEPTH);
+        assert!(decode_cbor(&data, DecodeMode::Strict).is_ok());
+        for data in [
+            nested(MAX_NESTING_DEPTH + 1),
+            nested(100_000),
+            vec![0x9f; 100_000],
+            vec![0xc1; 100_000],
+        ] {
+            assert!(matches!(
+                decode_cbor(&data, DecodeMode::Permissive),
+                Err(Error::NestingTooDeep(MAX_NESTING_DEPTH))
+            ));
+        }
+        // Siblings do not add to the depth.
+        let mut data = vec![0x98, 0xff];
+        data.extend(vec![0x80; 0xff]);
+        assert!(check_nesting(&data).is_ok());
+    }
+}
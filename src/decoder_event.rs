@@ -0,0 +1,27 @@
+import_stdlib!();
+
+/// Something that happened as a [`MultipartDecoder`](crate::MultipartDecoder)
+/// received a part, reported to the observer set with
+/// [`with_observer`](crate::MultipartDecoder::with_observer) so that a
+/// scanning UI can show it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecoderEvent {
+    /// The first part of a message of `fragment_count` fragments was
+    /// accepted.
+    Started { fragment_count: usize },
+    /// A part was accepted that recovered one or more fragments, bringing the
+    /// number recovered to `recovered` of `fragment_count`.
+    FragmentsRecovered {
+        recovered: usize,
+        fragment_count: usize,
+    },
+    /// The message was recovered and matches its checksum.
+    Completed,
+    /// A part was rejected with an error, whose message this is. The error
+    /// itself is returned by [`receive`](crate::MultipartDecoder::receive).
+    Rejected(String),
+    /// A part of a different message than the one in progress arrived, and
+    /// with [auto-restart](crate::MultipartDecoder::with_auto_restart) the
+    /// decoder discarded that message to begin the new one.
+    Restarted,
+}
@@ -0,0 +1,45 @@
+use core::time::Duration;
+
+/// Counts of what a [`MultipartDecoder`](crate::MultipartDecoder) has
+/// received, for diagnosing scanning problems.
+///
+/// The counts cover every message since the decoder was created, across
+/// [restarts](crate::MultipartDecoder::with_auto_restart) and
+/// [resets](crate::MultipartDecoder::reset).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DecoderStats {
+    pub(crate) frames_received: usize,
+    pub(crate) duplicate_frames: usize,
+    pub(crate) invalid_frames: usize,
+    pub(crate) mixed_parts: usize,
+    pub(crate) restarts: usize,
+    pub(crate) time_to_complete: Option<Duration>,
+}
+
+impl DecoderStats {
+    /// Returns the number of frames passed to the decoder, whatever became of
+    /// them.
+    pub fn frames_received(&self) -> usize { self.frames_received }
+
+    /// Returns the number of frames ignored because a part of the message in
+    /// progress with the same combination of fragments had already been
+    /// received.
+    pub fn duplicate_frames(&self) -> usize { self.duplicate_frames }
+
+    /// Returns the number of frames rejected with an error, such as frames
+    /// that are malformed, corrupt, or of another message.
+    pub fn invalid_frames(&self) -> usize { self.invalid_frames }
+
+    /// Returns the number of mixed parts, those past the pure fragments,
+    /// that were accepted and reduced against the parts before them.
+    pub fn mixed_parts(&self) -> usize { self.mixed_parts }
+
+    /// Returns the number of times a part of a new message discarded the
+    /// message in progress.
+    pub fn restarts(&self) -> usize { self.restarts }
+
+    /// Returns the time from the first part of the current message to its
+    /// completion, once it is complete. Always `None` without the `std`
+    /// feature.
+    pub fn time_to_complete(&self) -> Option<Duration> { self.time_to_complete }
+}
@@ -0,0 +1,90 @@
+import_stdlib!();
+
+use crate::xoshiro256::Xoshiro256;
+
+/// The order in which a [`MultipartEncoder`](crate::MultipartEncoder) emits
+/// parts.
+///
+/// Every strategy only changes which sequence numbers are emitted, so any
+/// standard decoder can reassemble the message.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum EmissionStrategy {
+    /// The fragments in order, then mixed parts without end.
+    #[default]
+    Standard,
+    /// As [`Standard`](Self::Standard), but starting at the given sequence
+    /// number, for example to resume where an earlier encoder left off.
+    StartAt(u32),
+    /// As [`Standard`](Self::Standard), but starting at a sequence number
+    /// chosen from the seed, so that displays given different seeds emit
+    /// different frames.
+    RandomStart(u64),
+    /// The fragments in order, repeated without end. Suits receivers that
+    /// cannot fountain decode, but a missed part costs a whole cycle.
+    PureOnly,
+    /// Cycles of the fragments in order, each followed by `ratio` times as
+    /// many new mixed parts, rounded up.
+    Redundancy(f64),
+}
+
+impl EmissionStrategy {
+    /// Returns the sequence number of the part emitted at `index`, counting
+    /// from zero, for a message of `seq_len` fragments.
+    ///
+    /// Sequence numbers past `u32::MAX` wrap around to 1.
+    pub(crate) fn seq_num(&self, index: u64, seq_len: usize) -> u32 {
+        let seq_len = seq_len as u64;
+        let seq_num = match *self {
+            Self::Standard => index + 1,
+            Self::StartAt(start) => index + start.max(1) as u64,
+            Self::RandomStart(seed) => {
+                let mut rng = Xoshiro256::from_data(&seed.to_be_bytes());
+                index + rng.next_int(1, 1 << 31)
+            }
+            Self::PureOnly => index % seq_len + 1,
+            Self::Redundancy(ratio) => {
+                // `as` saturates, and NaN and negative ratios become zero.
+                let mixed = (ratio * seq_len as f64).ceil() as u64;
+                let cycle_len = seq_len.saturating_add(mixed);
+                let (cycle, position) = (index / cycle_len, index % cycle_len);
+                if position < seq_len {
+                    position + 1
+                } else {
+                    seq_len + cycle * mixed + (position - seq_len) + 1
+                }
+            }
+        };
+        ((seq_num - 1) % u32::MAX as u64) as u32 + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seq_nums(strategy: EmissionStrategy, count: u64) -> Vec<u32> {
+        (0..count).map(|index| strategy.seq_num(index, 3)).collect()
+    }
+
+    #[test]
+    fn test_seq_nums() {
+        use EmissionStrategy::*;
+        assert_eq!(seq_nums(Standard, 6), [1, 2, 3, 4, 5, 6]);
+        assert_eq!(seq_nums(StartAt(10), 3), [10, 11, 12]);
+        assert_eq!(seq_nums(StartAt(0), 3), [1, 2, 3]);
+        assert_eq!(seq_nums(PureOnly, 7), [1, 2, 3, 1, 2, 3, 1]);
+        assert_eq!(
+            seq_nums(Redundancy(0.5), 12),
+            [1, 2, 3, 4, 5, 1, 2, 3, 6, 7, 1, 2]
+        );
+        assert_eq!(seq_nums(Redundancy(0.0), 4), [1, 2, 3, 1]);
+        assert_eq!(seq_nums(Redundancy(f64::NAN), 4), [1, 2, 3, 1]);
+        assert_eq!(seq_nums(Redundancy(f64::INFINITY), 4), [1, 2, 3, 4]);
+        assert_eq!(seq_nums(StartAt(u32::MAX), 2), [u32::MAX, 1]);
+
+        let a = seq_nums(RandomStart(1), 2);
+        assert_eq!(a[1], a[0] + 1);
+        assert_eq!(a, seq_nums(RandomStart(1), 2));
+        assert_ne!(a, seq_nums(RandomStart(2), 2));
+    }
+}
@@ -1,10 +1,16 @@
+import_stdlib!();
+
 use thiserror::Error;
 
+use crate::{Nonconformance, ParseError};
+
+/// The errors returned by this crate.
+///
+/// Some variants are only present with certain features, so the enum is
+/// non-exhaustive.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum Error {
-    #[error("UR decoder error ({0})")]
-    UR(String),
-
     #[error("Bytewords error ({0})")]
     Bytewords(String),
 
@@ -20,15 +26,67 @@ pub enum Error {
     #[error("invalid UR type")]
     InvalidType,
 
+    #[error("UR type begins or ends with a hyphen, or has two in a row")]
+    InvalidTypeHyphen,
+
+    #[error("UR type is longer than {0} characters")]
+    TypeTooLong(usize),
+
+    #[error("invalid multipart UR sequence")]
+    InvalidSequence,
+
     #[error("UR is not a single-part")]
     NotSinglePart,
 
+    #[error("UR is not a multipart")]
+    NotMultiPart,
+
+    #[error("expected non-empty message")]
+    EmptyMessage,
+
+    #[error("expected positive maximum fragment length")]
+    InvalidFragmentLen,
+
+    #[error("invalid multipart UR part")]
+    InvalidPart,
+
+    #[error("multipart UR part is inconsistent with previous parts")]
+    InconsistentPart,
+
+    #[error("multipart UR message does not match its checksum")]
+    ChecksumMismatch,
+
     #[error("expected UR type {0}, but found {1}")]
     UnexpectedType(String, String),
-}
 
-impl From<ur::ur::Error> for Error {
-    fn from(err: ur::ur::Error) -> Self { Error::UR(err.to_string()) }
+    #[error(transparent)]
+    Parse(ParseError),
+
+    #[error("UR type {0} is already registered with CBOR tag {1}")]
+    TypeAlreadyRegistered(String, u64),
+
+    #[error("CBOR tag {0} is already registered with UR type {1}")]
+    TagAlreadyRegistered(u64, String),
+
+    #[error("no UR type is known for CBOR tag {0}")]
+    UnknownTag(u64),
+
+    #[error("no decoder is registered for UR type {0}")]
+    UnsupportedType(String),
+
+    #[error("UR payload is not dCBOR: {0}")]
+    NonConformant(Nonconformance),
+
+    #[error("UR payload nests more than {0} levels deep")]
+    NestingTooDeep(usize),
+
+    #[cfg(feature = "codec")]
+    #[error("UR line is longer than {0} bytes")]
+    LineTooLong(usize),
+
+    #[cfg(feature = "codec")]
+    #[error("I/O error ({0})")]
+    Io(#[from] std::io::Error),
 }
 
 impl From<ur::bytewords::Error> for Error {
@@ -50,4 +108,4 @@ impl From<Error> for dcbor::Error {
     }
 }
 
-pub type Result<T> = std::result::Result<T, Error>;
+pub type Result<T> = StdResult<T, Error>;
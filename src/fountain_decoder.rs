@@ -0,0 +1,414 @@
+import_stdlib!();
+
+use crate::{
+    Error, FountainPart, Result, bytewords, fountain_utils::xor_into,
+    wipe::Wipe,
+};
+
+/// The message metadata that every part of one transmission shares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Params {
+    seq_len: usize,
+    message_len: usize,
+    checksum: u32,
+    fragment_len: usize,
+}
+
+impl Params {
+    fn of(part: &FountainPart) -> Self {
+        Self {
+            seq_len: part.seq_len(),
+            message_len: part.message_len(),
+            checksum: part.checksum(),
+            fragment_len: part.data().len(),
+        }
+    }
+}
+
+/// Reassembles a message from fountain code parts, per BCR-2020-005.
+///
+/// Parts may arrive in any order and with gaps. A simple part recovers its
+/// fragment directly. A mixed part is reduced by the fragments already
+/// recovered and kept until all but one of its fragments are known. This is
+/// the reduction of the reference decoder in the `ur` crate, so the decoder
+/// completes at the same part as it does.
+#[derive(Debug, Clone, Default)]
+pub struct FountainDecoder {
+    params: Option<Params>,
+    received: BTreeSet<Vec<usize>>,
+    /// The recovered fragments, by index.
+    fragments: BTreeMap<usize, Vec<u8>>,
+    /// The mixed parts not yet reduced to one fragment, keyed by the indexes
+    /// of the fragments they still mix.
+    mixed: BTreeMap<Vec<usize>, Vec<u8>>,
+    /// Recovered fragments not yet used to reduce the mixed parts. As in the
+    /// reference decoder, they are used when the next simple part arrives.
+    queue: Vec<(usize, Vec<u8>)>,
+    recovered_fragment_count: usize,
+    processed_parts_count: usize,
+    message: Option<Vec<u8>>,
+    checksum_failed: bool,
+}
+
+impl FountainDecoder {
+    pub fn new() -> Self { Self::default() }
+
+    /// Receives a part, returning `false` if it was ignored because it
+    /// duplicates a part already received or the decoder is complete.
+    ///
+    /// Returns [`Error::InvalidPart`] if the part is malformed or claims more
+    /// than 65,536 fragments, and [`Error::ChecksumMismatch`] if the part
+    /// completes the message but the message does not match its checksum.
+    pub fn receive(&mut self, part: &FountainPart) -> Result<bool> {
+        if self.is_complete() {
+            return Ok(false);
+        }
+        part.validate()?;
+
+        let params = Params::of(part);
+        match self.params {
+            None => self.params = Some(params),
+            Some(expected) if expected != params => {
+                return Err(Error::InconsistentPart);
+            }
+            Some(_) => {}
+        }
+
+        let indexes = part.indexes();
+        if !self.received.insert(indexes.clone()) {
+            return Ok(false);
+        }
+        self.processed_parts_count += 1;
+        let data = part.data().to_vec();
+        if let [index] = indexes[..] {
+            self.receive_simple(index, data);
+        } else {
+            self.receive_mixed(indexes, data);
+        }
+        if self.fragments.len() == params.seq_len {
+            self.finish(params)?;
+        }
+        Ok(true)
+    }
+
+    /// Returns `true` if the part has the same message length, checksum, and
+    /// fragment count and length as the parts already received, if any.
+    pub(crate) fn matches(&self, part: &FountainPart) -> bool {
+        self.params.is_none_or(|params| params == Params::of(part))
+    }
+
+    /// Returns `true` once the decoder has finished, either by recovering the
+    /// message or by finding that it does not match its checksum.
+    pub fn is_complete(&self) -> bool {
+        self.message.is_some() || self.checksum_failed
+    }
+
+    /// Returns the message if it has been recovered.
+    pub fn message(&self) -> Result<Option<&[u8]>> {
+        if self.checksum_failed {
+            return Err(Error::ChecksumMismatch);
+        }
+        Ok(self.message.as_deref())
+    }
+
+    /// Returns the number of fragments in the message, once a part has been
+    /// received.
+    pub fn expected_fragment_count(&self) -> Option<usize> {
+        self.params.map(|params| params.seq_len)
+    }
+
+    /// Returns the number of fragments recovered so far.
+    pub fn recovered_fragment_count(&self) -> usize {
+        self.recovered_fragment_count
+    }
+
+    /// Returns the number of distinct parts received.
+    pub fn processed_parts_count(&self) -> usize { self.processed_parts_count }
+
+    /// Estimates progress as a fraction in `[0, 1]`, assuming that about
+    /// 1.75 parts per fragment are needed.
+    pub fn estimated_percent_complete(&self) -> f64 {
+        if self.is_complete() {
+            return 1.0;
+        }
+        let Some(params) = self.params else {
+            return 0.0;
+        };
+        let estimated_parts = params.seq_len as f64 * 1.75;
+        (self.processed_parts_count as f64 / estimated_parts).min(0.99)
+    }
+
+    /// Records a recovered fragment and reduces the mixed parts by it and by
+    /// any fragments recovered from them in turn.
+    fn receive_simple(&mut self, index: usize, data: Vec<u8>) {
+        self.recover(index, data);
+        while let Some((index, mut fragment)) = self.queue.pop() {
+            let containing: Vec<Vec<usize>> = self
+                .mixed
+                .keys()
+                .filter(|indexes| indexes.contains(&index))
+                .cloned()
+                .collect();
+            for indexes in containing {
+                let mut data = self.mixed.remove(&indexes).unwrap();
+                xor_into(&mut data, &fragment);
+                let remaining: Vec<usize> =
+                    indexes.into_iter().filter(|&i| i != index).collect();
+                self.keep(remaining, data);
+            }
+            fragment.wipe();
+        }
+    }
+
+    /// Reduces a mixed part by the fragments already recovered and keeps it,
+    /// unless every fragment it mixes is already known.
+    fn receive_mixed(&mut self, indexes: Vec<usize>, mut data: Vec<u8>) {
+        let mut remaining = Vec::with_capacity(indexes.len());
+        for index in indexes {
+            match self.fragments.get(&index) {
+                Some(fragment) => xor_into(&mut data, fragment),
+                None => remaining.push(index),
+            }
+        }
+        if remaining.is_empty() {
+            data.wipe();
+            return;
+        }
+        self.keep(remaining, data);
+    }
+
+    /// Keeps a reduced part: as a recovered fragment if it mixes only one,
+    /// otherwise as a mixed part.
+    fn keep(&mut self, indexes: Vec<usize>, data: Vec<u8>) {
+        if let [index] = indexes[..] {
+            self.recover(index, data);
+        } else if let Some(mut replaced) = self.mixed.insert(indexes, data) {
+            replaced.wipe();
+        }
+    }
+
+    fn recover(&mut self, index: usize, data: Vec<u8>) {
+        match self.fragments.insert(index, data.clone()) {
+            Some(mut replaced) => replaced.wipe(),
+            None => self.recovered_fragment_count += 1,
+        }
+        self.queue.push((index, data));
+    }
+
+    /// Assembles the message once every fragment has been recovered.
+    fn finish(&mut self, params: Params) -> Result<()> {
+        self.wipe_pending();
+        let mut message =
+            Vec::with_capacity(params.seq_len * params.fragment_len);
+        for mut fragment in core::mem::take(&mut self.fragments).into_values() {
+            message.extend_from_slice(&fragment);
+            fragment.wipe();
+        }
+        message.truncate(params.message_len);
+        if bytewords::crc32(&message) != params.checksum {
+            message.wipe();
+            self.checksum_failed = true;
+            return Err(Error::ChecksumMismatch);
+        }
+        self.message = Some(message);
+        Ok(())
+    }
+
+    /// Wipes and discards the mixed parts and queued fragments.
+    fn wipe_pending(&mut self) {
+        for data in self.mixed.values_mut() {
+            data.wipe();
+        }
+        self.mixed.clear();
+        for (_, data) in &mut self.queue {
+            data.wipe();
+        }
+        self.queue.clear();
+    }
+}
+
+impl Drop for FountainDecoder {
+    fn drop(&mut self) {
+        self.wipe_pending();
+        for data in self.fragments.values_mut() {
+            data.wipe();
+        }
+        if let Some(message) = &mut self.message {
+            message.wipe();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FountainEncoder, xoshiro256::make_message};
+
+    #[test]
+    fn test_fountain_decoder() {
+        let message = make_message("Wolf", 32767);
+        let mut encoder = FountainEncoder::new(&message, 1000).unwrap();
+        let mut decoder = FountainDecoder::new();
+        while !decoder.is_complete() {
+            assert_eq!(decoder.message().unwrap(), None);
+            decoder.receive(&encoder.next_part()).unwrap();
+        }
+        assert_eq!(decoder.message().unwrap(), Some(message.as_slice()));
+        assert_eq!(decoder.estimated_percent_complete(), 1.0);
+    }
+
+    #[test]
+    fn test_fountain_decoder_mixed_parts_only() {
+        // Skip the pure parts, so every fragment must be recovered from the
+        // parts that follow them.
+        let message = make_message("Wolf", 32767);
+        let mut encoder = FountainEncoder::new(&message, 1000).unwrap();
+        let mut decoder = FountainDecoder::new();
+        while !decoder.is_complete() {
+            let part = encoder.next_part();
+            if part.seq_num() as usize <= part.seq_len() {
+                continue;
+            }
+            decoder.receive(&part).unwrap();
+        }
+        assert_eq!(decoder.message().unwrap(), Some(message.as_slice()));
+    }
+
+    #[test]
+    fn test_fountain_decoder_matches_reference() {
+        // Feed the same parts to this decoder and to the `ur` crate's, with
+        // various parts missing, and check that both complete at the same
+        // part. The encoders must produce the same parts for this to hold.
+        let message = make_message("Wolf", 1024);
+        for (fragment_len, start, skip) in [
+            (10, 1, 0),
+            (10, 51, 0),
+            (100, 1, 2),
+            (100, 5, 3),
+            (1024, 1, 0),
+        ] {
+            let mut encoder =
+                FountainEncoder::new(&message, fragment_len).unwrap();
+            let mut reference =
+                ur::fountain::Encoder::new(&message, fragment_len).unwrap();
+            let mut decoder = FountainDecoder::new();
+            let mut reference_decoder = ur::fountain::Decoder::default();
+            for seq_num in 1..=10_000 {
+                let part = encoder.next_part();
+                let reference_part = reference.next_part();
+                let mut reference_indexes = reference_part.indexes();
+                reference_indexes.sort_unstable();
+                assert_eq!(part.indexes(), reference_indexes);
+                assert_eq!(part.data(), reference_part.data());
+                if seq_num < start || (skip != 0 && seq_num % skip == 0) {
+                    continue;
+                }
+                decoder.receive(&part).unwrap();
+                reference_decoder.receive(reference_part).unwrap();
+                assert_eq!(
+                    decoder.is_complete(),
+                    reference_decoder.complete(),
+                    "fragment length {fragment_len}, part {seq_num}"
+                );
+                if decoder.is_complete() {
+                    break;
+                }
+            }
+            assert_eq!(decoder.message().unwrap(), Some(message.as_slice()));
+        }
+    }
+
+    #[test]
+    fn test_fountain_decoder_skip_some_simple_parts() {
+        let message = make_message("Wolf", 32767);
+        let mut encoder = FountainEncoder::new(&message, 1000).unwrap();
+        let mut decoder = FountainDecoder::new();
+        let mut skip = false;
+        while !decoder.is_complete() {
+            let part = encoder.next_part();
+            if !skip {
+                decoder.receive(&part).unwrap();
+            }
+            skip = !skip;
+        }
+        assert_eq!(decoder.message().unwrap(), Some(message.as_slice()));
+    }
+
+    #[test]
+    fn test_fountain_decoder_receive_result() {
+        let message = make_message("Wolf", 100);
+        let mut encoder = FountainEncoder::new(&message, 10).unwrap();
+        let mut decoder = FountainDecoder::new();
+        let part = encoder.next_part();
+        assert!(decoder.receive(&part).unwrap());
+        assert!(!decoder.receive(&part).unwrap());
+        assert_eq!(decoder.expected_fragment_count(), Some(10));
+        assert_eq!(decoder.recovered_fragment_count(), 1);
+        assert_eq!(decoder.processed_parts_count(), 1);
+
+        while !decoder.is_complete() {
+            decoder.receive(&encoder.next_part()).unwrap();
+        }
+        assert!(!decoder.receive(&encoder.next_part()).unwrap());
+    }
+
+    #[test]
+    fn test_fountain_decoder_inconsistent_part() {
+        let mut decoder = FountainDecoder::new();
+        let mut encoder = FountainEncoder::new(b"data", 3).unwrap();
+        decoder.receive(&encoder.next_part()).unwrap();
+
+        let mut other = FountainEncoder::new(b"more data", 3).unwrap();
+        assert!(matches!(
+            decoder.receive(&other.next_part()),
+            Err(Error::InconsistentPart)
+        ));
+    }
+
+    #[test]
+    fn test_fountain_decoder_invalid_part() {
+        let mut decoder = FountainDecoder::new();
+        for part in [
+            FountainPart::new(0, 1, 4, 0, vec![1, 2, 3, 4]),
+            FountainPart::new(1, 0, 4, 0, vec![1, 2, 3, 4]),
+            FountainPart::new(1, 1, 0, 0, vec![1, 2, 3, 4]),
+            FountainPart::new(1, 1, 4, 0, vec![]),
+            // Too many fragments for the message and fragment lengths
+            FountainPart::new(1, 1000, 4, 0, vec![1, 2, 3, 4]),
+            // More fragments than any encoder emits
+            FountainPart::new(
+                u32::MAX,
+                u32::MAX as usize,
+                u32::MAX as usize,
+                0,
+                vec![1],
+            ),
+        ] {
+            assert!(matches!(decoder.receive(&part), Err(Error::InvalidPart)));
+        }
+    }
+
+    #[test]
+    fn test_fountain_decoder_checksum_mismatch() {
+        let mut encoder = FountainEncoder::new(b"Ten chars!", 4).unwrap();
+        let mut decoder = FountainDecoder::new();
+        decoder.receive(&encoder.next_part()).unwrap();
+        decoder.receive(&encoder.next_part()).unwrap();
+        let part = encoder.next_part();
+        let mut data = part.data().to_vec();
+        data[0] ^= 1;
+        let corrupted = FountainPart::new(
+            part.seq_num(),
+            part.seq_len(),
+            part.message_len(),
+            part.checksum(),
+            data,
+        );
+        assert!(matches!(
+            decoder.receive(&corrupted),
+            Err(Error::ChecksumMismatch)
+        ));
+        assert!(decoder.is_complete());
+        assert!(matches!(decoder.message(), Err(Error::ChecksumMismatch)));
+    }
+}
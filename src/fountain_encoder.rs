@@ -0,0 +1,163 @@
+import_stdlib!();
+
+use crate::{
+    Error, FountainPart, Result, bytewords,
+    fountain_utils::{
+        MAX_FRAGMENT_COUNT, choose_fragments, fragment_len, partition, xor_into,
+    },
+    wipe::Wipe,
+};
+
+/// Splits a message into fragments and emits an unbounded stream of fountain
+/// code parts, per BCR-2020-005.
+///
+/// The first [`seq_len`](Self::seq_len) parts are the fragments in order.
+/// Each later part is the XOR of a pseudorandom selection of fragments, so a
+/// receiver can recover the message from any sufficiently large set of parts.
+#[derive(Debug, Clone)]
+pub struct FountainEncoder {
+    fragments: Vec<Vec<u8>>,
+    message_len: usize,
+    checksum: u32,
+    seq_num: u32,
+}
+
+impl FountainEncoder {
+    /// Creates an encoder for the message, using fragments of at most
+    /// `max_fragment_len` bytes.
+    ///
+    /// Returns [`Error::InvalidFragmentLen`] if the message would be split
+    /// into more than 65,536 fragments.
+    pub fn new(
+        message: impl AsRef<[u8]>,
+        max_fragment_len: usize,
+    ) -> Result<Self> {
+        let message = message.as_ref();
+        if message.is_empty() {
+            return Err(Error::EmptyMessage);
+        }
+        if max_fragment_len == 0 {
+            return Err(Error::InvalidFragmentLen);
+        }
+        let fragment_len = fragment_len(message.len(), max_fragment_len);
+        if message.len().div_ceil(fragment_len) > MAX_FRAGMENT_COUNT {
+            return Err(Error::InvalidFragmentLen);
+        }
+        Ok(Self {
+            fragments: partition(message, fragment_len),
+            message_len: message.len(),
+            checksum: bytewords::crc32(message),
+            seq_num: 0,
+        })
+    }
+
+    /// Returns the sequence number of the last part emitted, or zero if none
+    /// has been.
+    pub fn seq_num(&self) -> u32 { self.seq_num }
+
+    /// Returns the number of fragments the message was split into.
+    pub fn seq_len(&self) -> usize { self.fragments.len() }
+
+    /// Returns the length of the message in bytes.
+    pub fn message_len(&self) -> usize { self.message_len }
+
+    /// Returns the CRC-32 checksum of the message.
+    pub fn checksum(&self) -> u32 { self.checksum }
+
+    /// Returns the length of each fragment in bytes.
+    pub fn fragment_len(&self) -> usize { self.fragments[0].len() }
+
+    /// Returns `true` once every fragment has been emitted at least once, as
+    /// a simple part.
+    pub fn is_complete(&self) -> bool {
+        self.seq_num as usize >= self.fragments.len()
+    }
+
+    /// Returns `true` if the message fits in a single fragment.
+    pub fn is_single_part(&self) -> bool { self.fragments.len() == 1 }
+
+    /// Emits the next part.
+    pub fn next_part(&mut self) -> FountainPart {
+        self.emit(self.seq_num.checked_add(1).unwrap_or(1))
+    }
+
+    /// Emits the part with the given sequence number, which must not be
+    /// zero, continuing from there.
+    pub(crate) fn emit(&mut self, seq_num: u32) -> FountainPart {
+        self.seq_num = seq_num;
+        self.make_part(seq_num)
+    }
+
+    /// Returns the part with the given sequence number, without affecting
+    /// the encoder's position. Parts are a pure function of the message and
+    /// sequence number, so any part can be produced directly.
+    ///
+    /// Returns [`Error::InvalidSequence`] if `seq_num` is zero, as sequence
+    /// numbers start at 1.
+    pub fn part(&self, seq_num: u32) -> Result<FountainPart> {
+        if seq_num == 0 {
+            return Err(Error::InvalidSequence);
+        }
+        Ok(self.make_part(seq_num))
+    }
+
+    /// Returns the part with the given sequence number, which must not be
+    /// zero.
+    pub(crate) fn make_part(&self, seq_num: u32) -> FountainPart {
+        let indexes =
+            choose_fragments(seq_num, self.fragments.len(), self.checksum);
+        let mut data = vec![0; self.fragment_len()];
+        for index in indexes {
+            xor_into(&mut data, &self.fragments[index]);
+        }
+        FountainPart::new(
+            seq_num,
+            self.fragments.len(),
+            self.message_len,
+            self.checksum,
+            data,
+        )
+    }
+}
+
+impl Drop for FountainEncoder {
+    fn drop(&mut self) {
+        for fragment in &mut self.fragments {
+            fragment.wipe();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fountain_encoder_errors() {
+        assert!(matches!(
+            FountainEncoder::new(b"", 10),
+            Err(Error::EmptyMessage)
+        ));
+        assert!(matches!(
+            FountainEncoder::new(b"foo", 0),
+            Err(Error::InvalidFragmentLen)
+        ));
+        assert!(matches!(
+            FountainEncoder::new(vec![0; MAX_FRAGMENT_COUNT + 1], 1),
+            Err(Error::InvalidFragmentLen)
+        ));
+        assert!(FountainEncoder::new(vec![0; MAX_FRAGMENT_COUNT], 1).is_ok());
+    }
+
+    #[test]
+    fn test_part() {
+        let mut encoder = FountainEncoder::new(b"Ten chars!", 3).unwrap();
+        let parts: Vec<FountainPart> =
+            (0..50).map(|_| encoder.next_part()).collect();
+        for (seq_num, part) in (1..).zip(&parts) {
+            assert_eq!(&encoder.part(seq_num).unwrap(), part);
+        }
+        assert_eq!(encoder.seq_num(), 50);
+        assert!(matches!(encoder.part(0), Err(Error::InvalidSequence)));
+    }
+}
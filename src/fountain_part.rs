@@ -0,0 +1,189 @@
+import_stdlib!();
+
+use dcbor::prelude::*;
+
+use crate::{
+    Error, Result,
+    conformance::check_nesting,
+    fountain_utils::{MAX_FRAGMENT_COUNT, choose_fragments},
+    wipe::Wipe,
+};
+
+/// A single part emitted by a [`FountainEncoder`](crate::FountainEncoder).
+///
+/// A part carries either one fragment of the message or the XOR of several,
+/// together with the metadata a decoder needs to place it. Its CBOR encoding
+/// is the payload of a multipart UR.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FountainPart {
+    seq_num: u32,
+    seq_len: usize,
+    message_len: usize,
+    checksum: u32,
+    data: Vec<u8>,
+}
+
+impl FountainPart {
+    pub(crate) fn new(
+        seq_num: u32,
+        seq_len: usize,
+        message_len: usize,
+        checksum: u32,
+        data: Vec<u8>,
+    ) -> Self {
+        Self { seq_num, seq_len, message_len, checksum, data }
+    }
+
+    /// Decodes a part from the CBOR payload of a multipart UR.
+    ///
+    /// Returns [`Error::InvalidPart`] if the part's metadata is inconsistent:
+    /// if the sequence number is zero, there are no fragments or more than
+    /// 65,536, the message or data is empty, or splitting the message into
+    /// fragments of the data's length does not yield `seq_len` fragments.
+    pub fn from_cbor_data(data: impl AsRef<[u8]>) -> Result<Self> {
+        let data = data.as_ref();
+        check_nesting(data)?;
+        let cbor = CBOR::try_from_data(data)?;
+        let array = cbor.try_into_array()?;
+        let [seq_num, seq_len, message_len, checksum, data] =
+            <[CBOR; 5]>::try_from(array).map_err(|_| Error::InvalidPart)?;
+        let seq_num: u32 = seq_num.try_into()?;
+        let seq_len: u32 = seq_len.try_into()?;
+        let message_len: u32 = message_len.try_into()?;
+        let checksum: u32 = checksum.try_into()?;
+        let data = data.try_into_byte_string()?;
+        let part = Self::new(
+            seq_num,
+            seq_len as usize,
+            message_len as usize,
+            checksum,
+            data,
+        );
+        part.validate()?;
+        Ok(part)
+    }
+
+    /// Checks that the sequence number is nonzero, that there are between 1
+    /// and 65,536 fragments, that the message and data are not empty, and
+    /// that splitting the message into fragments of the data's length
+    /// yields `seq_len` fragments.
+    pub(crate) fn validate(&self) -> Result<()> {
+        let valid = self.seq_num != 0
+            && (1..=MAX_FRAGMENT_COUNT).contains(&self.seq_len)
+            && self.message_len != 0
+            && !self.data.is_empty()
+            && self.message_len.div_ceil(self.data.len()) == self.seq_len;
+        if valid {
+            Ok(())
+        } else {
+            Err(Error::InvalidPart)
+        }
+    }
+
+    /// Returns the CBOR encoding of the part.
+    pub fn cbor_data(&self) -> Vec<u8> {
+        CBOR::from(vec![
+            CBOR::from(self.seq_num),
+            CBOR::from(self.seq_len),
+            CBOR::from(self.message_len),
+            CBOR::from(self.checksum),
+            CBOR::to_byte_string(&self.data),
+        ])
+        .to_cbor_data()
+    }
+
+    /// Returns the one-based sequence number of the part.
+    pub fn seq_num(&self) -> u32 { self.seq_num }
+
+    /// Returns the number of fragments the message was split into.
+    pub fn seq_len(&self) -> usize { self.seq_len }
+
+    /// Returns the length of the original message in bytes.
+    pub fn message_len(&self) -> usize { self.message_len }
+
+    /// Returns the CRC-32 checksum of the original message.
+    pub fn checksum(&self) -> u32 { self.checksum }
+
+    /// Returns the fragment, or XOR of fragments, carried by the part.
+    pub fn data(&self) -> &[u8] { &self.data }
+
+    /// Returns the sorted indexes of the fragments mixed into this part.
+    pub fn indexes(&self) -> Vec<usize> {
+        let mut indexes =
+            choose_fragments(self.seq_num, self.seq_len, self.checksum);
+        indexes.sort_unstable();
+        indexes
+    }
+
+    /// Returns `true` if the part carries a single fragment.
+    pub fn is_simple(&self) -> bool { self.indexes().len() == 1 }
+
+    /// Returns the `seq_num-seq_len` component of the part's UR string.
+    pub fn sequence_id(&self) -> String {
+        format!("{}-{}", self.seq_num, self.seq_len)
+    }
+}
+
+impl Drop for FountainPart {
+    fn drop(&mut self) { self.data.wipe(); }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part_cbor() {
+        let part =
+            FountainPart::new(12, 20, 100, 0x12345678, vec![1, 5, 3, 3, 5]);
+        let data = part.cbor_data();
+        assert_eq!(hex::encode(&data), "850c1418641a12345678450105030305");
+        assert_eq!(FountainPart::from_cbor_data(&data).unwrap(), part);
+    }
+
+    #[test]
+    fn test_part_cbor_errors() {
+        // Not an array
+        assert!(
+            FountainPart::from_cbor_data(hex::decode("18").unwrap()).is_err()
+        );
+        // Array of the wrong length
+        assert!(matches!(
+            FountainPart::from_cbor_data(hex::decode("8401020340").unwrap()),
+            Err(Error::InvalidPart)
+        ));
+        // Sequence number too large for a u32
+        assert!(
+            FountainPart::from_cbor_data(
+                hex::decode("851b00000001000000000102030440").unwrap()
+            )
+            .is_err()
+        );
+        // Data is not a byte string
+        assert!(
+            FountainPart::from_cbor_data(hex::decode("850102030401").unwrap())
+                .is_err()
+        );
+        // Well-formed but inconsistent metadata, which would otherwise make
+        // `indexes` panic or allocate for billions of fragments.
+        for part in [
+            FountainPart::new(0, 1, 4, 0, vec![1, 2, 3, 4]),
+            FountainPart::new(1, 0, 4, 0, vec![1, 2, 3, 4]),
+            FountainPart::new(1, 1, 0, 0, vec![1, 2, 3, 4]),
+            FountainPart::new(1, 1, 4, 0, vec![]),
+            FountainPart::new(1, 1000, 4, 0, vec![1, 2, 3, 4]),
+            FountainPart::new(
+                u32::MAX,
+                u32::MAX as usize,
+                u32::MAX as usize,
+                0,
+                vec![1],
+            ),
+        ] {
+            assert!(matches!(
+                FountainPart::from_cbor_data(part.cbor_data()),
+                Err(Error::InvalidPart)
+            ));
+        }
+    }
+}
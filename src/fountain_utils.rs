@@ -0,0 +1,160 @@
+import_stdlib!();
+
+use crate::xoshiro256::Xoshiro256;
+
+/// The most fragments a message may be split into. Choosing the fragments of
+/// a mixed part takes time and memory proportional to the fragment count, so
+/// decoders reject parts that claim more.
+pub(crate) const MAX_FRAGMENT_COUNT: usize = 1 << 16;
+
+/// Returns the fragment length that splits `message_len` bytes into the fewest
+/// fragments of at most `max_fragment_len` bytes, with as little padding as
+/// possible.
+pub(crate) fn fragment_len(
+    message_len: usize,
+    max_fragment_len: usize,
+) -> usize {
+    let fragment_count = message_len.div_ceil(max_fragment_len);
+    message_len.div_ceil(fragment_count)
+}
+
+/// Splits the message into fragments of `fragment_len` bytes, zero-padding
+/// the last one.
+pub(crate) fn partition(message: &[u8], fragment_len: usize) -> Vec<Vec<u8>> {
+    message
+        .chunks(fragment_len)
+        .map(|chunk| {
+            let mut fragment = chunk.to_vec();
+            fragment.resize(fragment_len, 0);
+            fragment
+        })
+        .collect()
+}
+
+/// Returns the indexes of the fragments mixed into part `seq_num` of a
+/// message with `seq_len` fragments and the given checksum.
+///
+/// The first `seq_len` parts are the fragments in order. Later parts mix a
+/// pseudorandom selection seeded from the sequence number and checksum.
+///
+/// Choosing takes `O(seq_len + degree * log seq_len)` time for a mixed part,
+/// where the degree is at most `seq_len`.
+pub(crate) fn choose_fragments(
+    seq_num: u32,
+    seq_len: usize,
+    checksum: u32,
+) -> Vec<usize> {
+    if seq_num as usize <= seq_len {
+        return vec![seq_num as usize - 1];
+    }
+
+    let mut seed = [0u8; 8];
+    seed[..4].copy_from_slice(&seq_num.to_be_bytes());
+    seed[4..].copy_from_slice(&checksum.to_be_bytes());
+    let mut rng = Xoshiro256::from_data(&seed);
+    let degree = rng.choose_degree(seq_len);
+    // The first `degree` picks of a full shuffle, without making the rest.
+    let mut remaining = Remaining::new(seq_len);
+    (0..degree)
+        .map(|_| {
+            let index = rng.next_int(0, (remaining.len() - 1) as u64);
+            remaining.take(index as usize)
+        })
+        .collect()
+}
+
+/// The fragments not yet chosen by a shuffle, in ascending order.
+///
+/// The shuffle takes the `n`th remaining fragment at each step. A Fenwick
+/// tree of the remaining fragments finds and removes it in `O(log len)` time,
+/// where removing it from a list would shift every fragment after it.
+struct Remaining {
+    /// `tree[i]` counts the remaining fragments among `i - (i & -i)..i`.
+    tree: Vec<usize>,
+    len: usize,
+}
+
+impl Remaining {
+    fn new(len: usize) -> Self {
+        let tree = (0..=len).map(|i| i & i.wrapping_neg()).collect();
+        Self { tree, len }
+    }
+
+    fn len(&self) -> usize { self.len }
+
+    /// Removes and returns the `n`th remaining fragment, counting from 0.
+    fn take(&mut self, mut n: usize) -> usize {
+        // Find the longest run of fragments from 0 in which at most `n`
+        // remain; the fragment after it is the `n`th remaining.
+        let mut fragment = 0;
+        let mut step = (self.tree.len() - 1).next_power_of_two();
+        while step > 0 {
+            let next = fragment + step;
+            if next < self.tree.len() && self.tree[next] <= n {
+                fragment = next;
+                n -= self.tree[next];
+            }
+            step /= 2;
+        }
+        let mut i = fragment + 1;
+        while i < self.tree.len() {
+            self.tree[i] -= 1;
+            i += i & i.wrapping_neg();
+        }
+        self.len -= 1;
+        fragment
+    }
+}
+
+/// XORs `other` into `data`, which must have the same length.
+pub(crate) fn xor_into(data: &mut [u8], other: &[u8]) {
+    debug_assert_eq!(data.len(), other.len());
+    for (a, b) in data.iter_mut().zip(other) {
+        *a ^= b;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fragment_len() {
+        assert_eq!(fragment_len(12345, 1955), 1764);
+        assert_eq!(fragment_len(12345, 30000), 12345);
+        assert_eq!(fragment_len(10, 4), 4);
+        assert_eq!(fragment_len(10, 5), 5);
+        assert_eq!(fragment_len(10, 6), 5);
+        assert_eq!(fragment_len(10, 10), 10);
+    }
+
+    #[test]
+    fn test_remaining() {
+        // Taking from the tree matches removing from a list.
+        let mut rng = Xoshiro256::from_data(b"Wolf");
+        for len in [1, 2, 3, 7, 8, 9, 100, 1000] {
+            let mut remaining = Remaining::new(len);
+            let mut list: Vec<usize> = (0..len).collect();
+            while !list.is_empty() {
+                let n = rng.next_int(0, (list.len() - 1) as u64) as usize;
+                assert_eq!(remaining.take(n), list.remove(n));
+                assert_eq!(remaining.len(), list.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_xor() {
+        let mut rng = Xoshiro256::from_data(b"Wolf");
+        let data1 = rng.next_data(10);
+        assert_eq!(hex::encode(&data1), "916ec65cf77cadf55cd7");
+        let data2 = rng.next_data(10);
+        assert_eq!(hex::encode(&data2), "f9cda1a1030026ddd42e");
+
+        let mut data3 = data1.clone();
+        xor_into(&mut data3, &data2);
+        assert_eq!(hex::encode(&data3), "68a367fdf47c8b2888f9");
+        xor_into(&mut data3, &data1);
+        assert_eq!(data3, data2);
+    }
+}
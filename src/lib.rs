@@ -1,5 +1,6 @@
-#![doc(html_root_url = "https://docs.rs/bc-ur/0.19.2")]
+#![doc(html_root_url = "https://docs.rs/bc-ur/0.20.0")]
 #![warn(rust_2018_idioms)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
 //! # Blockchain Commons Uniform Resources ("UR") for Rust
 //!
@@ -7,10 +8,11 @@
 //! (URs)](https://github.com/BlockchainCommons/Research/blob/master/papers/bcr-2020-005-ur.md)
 //! are URI-encoded [CBOR](https://cbor.io) structures developed by [Blockchain
 //! Commons](https://blockchaincommons.com). This crate is an opinionated
-//! wrapper around the [ur](https://crates.io/crates/ur) crate by [Dominik
-//! Spicher](https://github.com/dspicher), and is intended primarily for use in
-//! higher-level Blockchain Commmons projects like [Gordian
-//! Envelope](https://crates.io/crates/bc-envelope).
+//! implementation of URs, including the fountain codes of multipart URs, that
+//! uses the [ur](https://crates.io/crates/ur) crate by [Dominik
+//! Spicher](https://github.com/dspicher) for bytewords. It is intended
+//! primarily for use in higher-level Blockchain Commmons projects like
+//! [Gordian Envelope](https://crates.io/crates/bc-envelope).
 //!
 //! It is a requirement of the UR specification that the CBOR encoded as URs
 //! conform to Gordian dCBOR, which is a deterministic profile of CBOR currently
@@ -22,9 +24,15 @@
 //! `CBORTaggedDecodable`. It is strongly recommended that adopters of URs
 //! implement these traits for their types.
 //!
-//! This crate does not currenly provide opinionated affordances for multi-part
-//! URs using fountain codes, but the dependency `ur` crate can be used directly
-//! for that purpose.
+//! Multipart URs are supported by `MultipartEncoder` and `MultipartDecoder`,
+//! which are built on this crate's own fountain code implementation,
+//! `FountainEncoder` and `FountainDecoder`. `MultipartEncoder` is an endless
+//! `Iterator` of parts, and with the `async` feature its `into_stream` method
+//! emits them on a timer as a `futures` `Stream`, for animated QR codes. The
+//! `codec` feature adds `URCodec`, a `tokio-util` codec that frames single and
+//! multipart URs as lines over byte streams. `Simulation` estimates how many
+//! frames a receiver needs over a lossy channel, for tuning fragment lengths
+//! and frame rates.
 //!
 //! # Getting Started
 //!
@@ -32,9 +40,42 @@
 //!
 //! ```toml
 //! [dependencies]
-//! bc-ur = "0.19.1"
+//! bc-ur = "0.20.0"
 //! ```
 //!
+//! # `no_std` Support
+//!
+//! The crate builds without the standard library for embedded signers. Disable
+//! the default `std` feature and enable `no_std`, which requires `alloc`:
+//!
+//! ```toml
+//! [dependencies]
+//! bc-ur = { version = "0.20.0", default-features = false, features = ["no_std"] }
+//! ```
+//!
+//! UR encoding and decoding, bytewords, and multipart encoding and decoding
+//! are all available. The `std::io` writers such as `UR::write_to_io` are
+//! omitted, and a `RawUR` caches its decoded payload in a `OnceCell`, so it
+//! is not `Sync`.
+//!
+//! # Sensitive Payloads
+//!
+//! URs often carry seeds and private keys. The `Debug` output of a UR whose
+//! type is sensitive, such as `seed`, is redacted, as is the `Display` output
+//! of its `redacted()` adapter, while `Display` of the UR itself writes the
+//! full UR string; see `SENSITIVE_UR_TYPES` and `mark_ur_type_sensitive`. With
+//! the `zeroize` feature, the buffers this crate fills with a payload while
+//! encoding and decoding, including those of `RawUR` and the fountain encoder
+//! and decoder, are cleared before they are freed:
+//!
+//! ```toml
+//! [dependencies]
+//! bc-ur = { version = "0.20.0", features = ["zeroize"] }
+//! ```
+//!
+//! Strings returned to the caller, `CBOR` values, and buffers inside the `ur`
+//! and `dcbor` crates are not cleared.
+//!
 //! # Specification
 //!
 //! The primary specification for URs is [BCR-2020-005:
@@ -75,19 +116,64 @@
 //! # }
 //! ```
 
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[macro_use]
+mod stdlib;
+
+mod wipe;
+
 mod ur;
-pub use ur::UR;
+pub use ur::{Case, UR};
+
+mod raw_ur;
+pub use raw_ur::RawUR;
 
 pub mod bytewords;
 
 mod ur_type;
 pub use ur_type::URType;
 
+mod ur_literal;
+#[doc(hidden)]
+pub use ur_literal::check_ur_literal;
+
+mod ur_type_registry;
+pub use ur_type_registry::{
+    KNOWN_UR_TYPES, SENSITIVE_UR_TYPES, URTypeEntry, URTypeRegistry,
+    mark_ur_type_sensitive, register_ur_type, with_ur_types, with_ur_types_mut,
+};
+
 mod error;
 pub use error::{Error, Result};
 
-mod utils;
-pub(crate) use utils::*;
+mod parse_error;
+pub use parse_error::{ParseError, URSegment};
+
+mod ur_parser;
+
+mod conformance;
+pub use conformance::{DecodeMode, Nonconformance, NonconformanceKind};
+
+mod fountain_part;
+mod fountain_utils;
+mod random_sampler;
+mod xoshiro256;
+pub use fountain_part::FountainPart;
+mod fountain_encoder;
+pub use fountain_encoder::FountainEncoder;
+mod fountain_decoder;
+pub use fountain_decoder::FountainDecoder;
+
+#[cfg(test)]
+mod test_vectors;
+
+#[cfg(test)]
+mod property_tests;
+
+#[cfg(test)]
+mod test_fixtures;
 
 mod ur_encodable;
 pub use ur_encodable::UREncodable;
@@ -98,12 +184,40 @@ pub use ur_decodable::URDecodable;
 mod ur_codable;
 pub use ur_codable::URCodable;
 
+mod typed_ur;
+pub use typed_ur::TypedUR;
+
+mod ur_dispatcher;
+pub use ur_dispatcher::URDispatcher;
+
 mod multipart_decoder;
 pub use multipart_decoder::MultipartDecoder;
 
 mod multipart_encoder;
 pub use multipart_encoder::MultipartEncoder;
 
+mod emission_strategy;
+pub use emission_strategy::EmissionStrategy;
+
+mod decoder_event;
+pub use decoder_event::DecoderEvent;
+
+mod decoder_stats;
+pub use decoder_stats::DecoderStats;
+
+mod simulation;
+pub use simulation::{Simulation, SimulationReport};
+
+#[cfg(feature = "async")]
+mod part_stream;
+#[cfg(feature = "async")]
+pub use part_stream::PartStream;
+
+#[cfg(feature = "codec")]
+mod ur_codec;
+#[cfg(feature = "codec")]
+pub use ur_codec::URCodec;
+
 pub mod prelude;
 
 #[cfg(test)]
@@ -166,6 +280,21 @@ mod example_tests {
         encoder.current_index()
     }
 
+    #[test]
+    fn test_fountain_raw() {
+        let message = "The only thing we have to fear is fear itself.";
+        let ur =
+            UR::new("bytes", CBOR::to_byte_string(message.as_bytes())).unwrap();
+        let raw = RawUR::from(&ur);
+
+        let mut encoder = MultipartEncoder::from_raw(&raw, 10).unwrap();
+        let mut decoder = MultipartDecoder::new();
+        while !decoder.is_complete() {
+            decoder.receive(&encoder.next_part().unwrap()).unwrap();
+        }
+        assert_eq!(decoder.message().unwrap().unwrap(), ur);
+    }
+
     #[test]
     fn test_fountain() {
         assert_eq!(run_fountain_test(1), 5);
@@ -1,15 +1,84 @@
-use dcbor::prelude::*;
+import_stdlib!();
 
-use crate::{Error, Result, UR, URType};
+use crate::{
+    DecodeMode, DecoderEvent, DecoderStats, Error, FountainDecoder,
+    FountainPart, Result, UR, URType, conformance::decode_cbor, ur_parser,
+    wipe::Wiped,
+};
+
+type Observer = Box<dyn FnMut(&DecoderEvent) + Send + Sync>;
 
 pub struct MultipartDecoder {
     ur_type: Option<URType>,
-    decoder: ur::Decoder,
+    decoder: FountainDecoder,
+    auto_restart: bool,
+    observer: Option<Observer>,
+    stats: DecoderStats,
+    /// When the first part of the current message was accepted.
+    #[cfg(feature = "std")]
+    started_at: Option<std::time::Instant>,
 }
 
 impl MultipartDecoder {
     pub fn new() -> Self {
-        Self { ur_type: None, decoder: ur::Decoder::default() }
+        Self {
+            ur_type: None,
+            decoder: FountainDecoder::new(),
+            auto_restart: false,
+            observer: None,
+            stats: DecoderStats::default(),
+            #[cfg(feature = "std")]
+            started_at: None,
+        }
+    }
+
+    /// Sets whether a part of a different message than the one in progress,
+    /// by its type, length, or checksum, discards that message and begins the
+    /// new one, as when the user turns to scan another animated QR code. This
+    /// also applies once a message is complete. A
+    /// [`DecoderEvent::Restarted`] is reported for each restart.
+    ///
+    /// Without auto-restart, which is the default, such a part is rejected
+    /// with [`Error::UnexpectedType`] or [`Error::InconsistentPart`], and once
+    /// a message is complete further parts are ignored until
+    /// [`reset`](Self::reset).
+    pub fn with_auto_restart(mut self, auto_restart: bool) -> Self {
+        self.auto_restart = auto_restart;
+        self
+    }
+
+    /// Returns whether a part of a different message restarts decoding.
+    pub fn auto_restart(&self) -> bool { self.auto_restart }
+
+    /// Calls `observer` with each [`DecoderEvent`].
+    pub fn with_observer(
+        mut self,
+        observer: impl FnMut(&DecoderEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.observer = Some(Box::new(observer));
+        self
+    }
+
+    /// Discards the message in progress, or the completed message, so that
+    /// the next part received begins a new one. The auto-restart setting,
+    /// observer, and statistics are kept.
+    pub fn reset(&mut self) {
+        self.ur_type = None;
+        self.decoder = FountainDecoder::new();
+        self.stats.time_to_complete = None;
+        #[cfg(feature = "std")]
+        {
+            self.started_at = None;
+        }
+    }
+
+    /// Returns counts of the frames received so far.
+    pub fn stats(&self) -> &DecoderStats { &self.stats }
+
+    fn notify(&mut self, event: DecoderEvent) {
+        if let Some(observer) = &mut self.observer {
+            observer(&event);
+        }
     }
 }
 
@@ -18,41 +87,350 @@ impl Default for MultipartDecoder {
 }
 
 impl MultipartDecoder {
+    /// Receives a multipart UR string, in either case.
     pub fn receive(&mut self, value: &str) -> Result<()> {
-        let decoded_type = Self::decode_type(value)?;
-        if let Some(ur_type) = &self.ur_type {
-            if ur_type != &decoded_type {
-                return Err(Error::UnexpectedType(
-                    ur_type.string().to_string(),
-                    decoded_type.string().to_string(),
-                ));
+        self.stats.frames_received += 1;
+        let result = self.receive_part(value);
+        if let Err(err) = &result {
+            self.reject(err);
+        }
+        result
+    }
+
+    /// Counts a frame that was received, such as a line of a stream, but
+    /// rejected before it reached [`receive`](Self::receive).
+    #[cfg(feature = "codec")]
+    pub(crate) fn reject_frame(&mut self, err: &Error) {
+        self.stats.frames_received += 1;
+        self.reject(err);
+    }
+
+    /// Counts the last frame received as invalid and reports `err`.
+    pub(crate) fn reject(&mut self, err: &Error) {
+        self.stats.invalid_frames += 1;
+        self.notify(DecoderEvent::Rejected(err.to_string()));
+    }
+
+    fn receive_part(&mut self, value: &str) -> Result<()> {
+        let parsed = ur_parser::parse_ur(&Wiped(value.to_ascii_lowercase()))?;
+        let payload = Wiped(parsed.payload);
+        let decoded_type = parsed.ur_type;
+        let (seq_num, seq_len) = parsed.sequence.ok_or(Error::NotMultiPart)?;
+        // The part is validated here, so that a malformed frame is rejected
+        // before it can restart the message in progress.
+        let part = FountainPart::from_cbor_data(&*payload)?;
+        if part.seq_num() != seq_num || part.seq_len() != seq_len as usize {
+            return Err(Error::InvalidSequence);
+        }
+        let same_type =
+            self.ur_type.as_ref().is_none_or(|t| t == &decoded_type);
+        let started = self.decoder.processed_parts_count() > 0;
+        if self.auto_restart
+            && started
+            && !(same_type && self.decoder.matches(&part))
+        {
+            self.reset();
+            self.stats.restarts += 1;
+            self.notify(DecoderEvent::Restarted);
+        } else if let Some(ur_type) = &self.ur_type
+            && !same_type
+        {
+            return Err(Error::UnexpectedType(
+                ur_type.string().to_string(),
+                decoded_type.string().to_string(),
+            ));
+        }
+        let was_complete = self.decoder.is_complete();
+        let recovered = self.decoder.recovered_fragment_count();
+        let accepted = self.decoder.receive(&part)?;
+        // Only a part that was accepted fixes the type of the message.
+        self.ur_type.get_or_insert(decoded_type);
+        if !accepted {
+            if !was_complete {
+                self.stats.duplicate_frames += 1;
+            }
+            return Ok(());
+        }
+        self.on_accepted(&part, recovered);
+        Ok(())
+    }
+
+    /// Updates the statistics and reports events for a part that was
+    /// accepted, when `recovered` fragments had been recovered before it.
+    fn on_accepted(&mut self, part: &FountainPart, recovered: usize) {
+        let fragment_count = part.seq_len();
+        if part.seq_num() as usize > fragment_count {
+            self.stats.mixed_parts += 1;
+        }
+        if self.decoder.processed_parts_count() == 1 {
+            #[cfg(feature = "std")]
+            {
+                self.started_at = Some(std::time::Instant::now());
             }
-        } else {
-            self.ur_type = Some(decoded_type);
+            self.notify(DecoderEvent::Started { fragment_count });
+        }
+        let now_recovered = self.decoder.recovered_fragment_count();
+        if now_recovered > recovered {
+            self.notify(DecoderEvent::FragmentsRecovered {
+                recovered: now_recovered,
+                fragment_count,
+            });
+        }
+        if self.decoder.is_complete() {
+            #[cfg(feature = "std")]
+            {
+                self.stats.time_to_complete =
+                    self.started_at.map(|started_at| started_at.elapsed());
+            }
+            self.notify(DecoderEvent::Completed);
         }
-        Ok(self.decoder.receive(value)?)
     }
 
-    pub fn is_complete(&self) -> bool { self.decoder.complete() }
+    /// Receives the raw payload of a scanned QR code, such as
+    /// [`UR::qr_data`] returns.
+    pub fn receive_bytes(&mut self, data: &[u8]) -> Result<()> {
+        // Bytes that are not UTF-8 cannot be in a UR, and are reported by the
+        // parser at their offset.
+        self.receive(&String::from_utf8_lossy(data))
+    }
+
+    pub fn is_complete(&self) -> bool { self.decoder.is_complete() }
 
     pub fn message(&self) -> Result<Option<UR>> {
-        let message_data = self.decoder.message()?;
-        if let Some(data) = message_data {
-            let cbor = CBOR::try_from_data(data)?;
-            let ur_type = self.ur_type.as_ref().unwrap();
-            let ur_type_string = ur_type.string();
-            let ur = UR::new(ur_type_string, cbor)?;
-            Ok(Some(ur))
-        } else {
-            Ok(None)
-        }
-    }
-
-    fn decode_type(ur_string: &str) -> Result<URType> {
-        let without_scheme =
-            ur_string.strip_prefix("ur:").ok_or(Error::InvalidScheme)?;
-        let first_component =
-            without_scheme.split('/').next().ok_or(Error::InvalidType)?;
-        URType::new(first_component)
+        let (Some(data), Some(ur_type)) =
+            (self.decoder.message()?, &self.ur_type)
+        else {
+            return Ok(None);
+        };
+        let (cbor, _) = decode_cbor(data, DecodeMode::Strict)?;
+        Ok(Some(UR::from_parts(ur_type.clone(), cbor)))
+    }
+
+    /// Returns the underlying fountain decoder.
+    pub fn fountain_decoder(&self) -> &FountainDecoder { &self.decoder }
+}
+
+#[cfg(test)]
+mod tests {
+    use dcbor::prelude::*;
+
+    use super::*;
+    use crate::MultipartEncoder;
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<MultipartDecoder>();
+    }
+
+    #[test]
+    fn test_multipart_decoder_case() {
+        let message = "The only thing we have to fear is fear itself.";
+        let ur =
+            UR::new("bytes", CBOR::to_byte_string(message.as_bytes())).unwrap();
+        let mut encoder = MultipartEncoder::new(&ur, 10).unwrap();
+        let mut decoder = MultipartDecoder::new();
+        let mut upper = true;
+        while !decoder.is_complete() {
+            let part = encoder.next_part().unwrap();
+            if upper {
+                decoder.receive(&part.to_ascii_uppercase()).unwrap();
+            } else {
+                decoder.receive_bytes(part.as_bytes()).unwrap();
+            }
+            upper = !upper;
+        }
+        assert_eq!(decoder.message().unwrap(), Some(ur));
+
+        let mut decoder = MultipartDecoder::new();
+        assert!(matches!(
+            decoder.receive_bytes(b"UR:BYTES/1-5/\xff"),
+            Err(Error::Parse(err)) if err.offset() == 13
+        ));
+    }
+
+    #[test]
+    fn test_restart() {
+        use std::sync::{Arc, Mutex};
+
+        let ur = |message: &str| {
+            UR::new("bytes", CBOR::to_byte_string(message.as_bytes())).unwrap()
+        };
+        let first = ur("The only thing we have to fear is fear itself.");
+        let second = ur("Ask not what your country can do for you.");
+        let first_parts = MultipartEncoder::new(&first, 10).unwrap();
+        let mut second_parts = MultipartEncoder::new(&second, 10).unwrap();
+
+        let mut decoder = MultipartDecoder::new();
+        decoder.receive(&first_parts.part(1).unwrap()).unwrap();
+        assert!(matches!(
+            decoder.receive(&second_parts.part(1).unwrap()),
+            Err(Error::InconsistentPart)
+        ));
+        decoder.reset();
+        decoder.receive(&second_parts.part(1).unwrap()).unwrap();
+        assert_eq!(decoder.fountain_decoder().processed_parts_count(), 1);
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let observed = events.clone();
+        let mut decoder = MultipartDecoder::new()
+            .with_auto_restart(true)
+            .with_observer(move |event| {
+                observed.lock().unwrap().push(event.clone())
+            });
+        assert!(decoder.auto_restart());
+        for part in first_parts.pure_parts().iter().take(3) {
+            decoder.receive(part).unwrap();
+        }
+        while !decoder.is_complete() {
+            decoder.receive(&second_parts.next_part().unwrap()).unwrap();
+        }
+        assert_eq!(decoder.message().unwrap(), Some(second));
+        let restarts = || {
+            let events = events.lock().unwrap();
+            events
+                .iter()
+                .filter(|e| **e == DecoderEvent::Restarted)
+                .count()
+        };
+        assert_eq!(restarts(), 1);
+
+        // A new message also replaces a completed one.
+        decoder.receive(&first_parts.part(1).unwrap()).unwrap();
+        assert!(!decoder.is_complete());
+        assert_eq!(restarts(), 2);
+
+        // So does a message of another type.
+        let other = UR::new("other", CBOR::to_byte_string([0; 100])).unwrap();
+        let other_parts = MultipartEncoder::new(&other, 10).unwrap();
+        decoder.receive(&other_parts.part(1).unwrap()).unwrap();
+        assert_eq!(restarts(), 3);
+        assert_eq!(decoder.stats().restarts(), 3);
+    }
+
+    #[test]
+    fn test_malformed_part_does_not_restart() {
+        use crate::{bytewords::Style, fountain_part::FountainPart};
+
+        let ur = UR::new(
+            "bytes",
+            CBOR::to_byte_string(
+                b"The only thing we have to fear is fear itself.",
+            ),
+        )
+        .unwrap();
+        let encoder = MultipartEncoder::new(&ur, 10).unwrap();
+        let mut decoder = MultipartDecoder::new().with_auto_restart(true);
+        decoder.receive(&encoder.part(1).unwrap()).unwrap();
+        decoder.receive(&encoder.part(2).unwrap()).unwrap();
+
+        // Well-formed CBOR, but 100 bytes do not make 3 fragments of 10.
+        let malformed = FountainPart::new(1, 3, 100, 0, vec![0; 10]);
+        let frame = format!(
+            "ur:bytes/1-3/{}",
+            crate::bytewords::encode(malformed.cbor_data(), Style::Minimal)
+        );
+        assert!(matches!(decoder.receive(&frame), Err(Error::InvalidPart)));
+
+        let mut seq_num = 3;
+        while !decoder.is_complete() {
+            decoder.receive(&encoder.part(seq_num).unwrap()).unwrap();
+            seq_num += 1;
+        }
+        assert_eq!(decoder.message().unwrap(), Some(ur));
+        assert_eq!(decoder.stats().restarts(), 0);
+        assert_eq!(decoder.stats().invalid_frames(), 1);
+    }
+
+    #[test]
+    fn test_stats_and_events() {
+        use std::sync::{Arc, Mutex};
+
+        let message = "The only thing we have to fear is fear itself.";
+        let ur =
+            UR::new("bytes", CBOR::to_byte_string(message.as_bytes())).unwrap();
+        let encoder = MultipartEncoder::new(&ur, 10).unwrap();
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let observed = events.clone();
+        let mut decoder = MultipartDecoder::new().with_observer(move |event| {
+            observed.lock().unwrap().push(event.clone())
+        });
+        decoder.receive(&encoder.part(1).unwrap()).unwrap();
+        decoder.receive(&encoder.part(1).unwrap()).unwrap();
+        assert!(decoder.receive("ur:bytes/1-5/nonsense").is_err());
+        let mut seq_num = 6;
+        while !decoder.is_complete() {
+            decoder.receive(&encoder.part(seq_num).unwrap()).unwrap();
+            seq_num += 1;
+        }
+        // Parts after completion are neither duplicates nor invalid.
+        decoder.receive(&encoder.part(1).unwrap()).unwrap();
+
+        // Some mixed parts repeat a combination of fragments already
+        // received, and are duplicates too.
+        let mixed_frames = seq_num as usize - 6;
+        let stats = decoder.stats();
+        assert_eq!(stats.frames_received(), mixed_frames + 4);
+        assert_eq!(stats.invalid_frames(), 1);
+        assert!(stats.duplicate_frames() >= 1);
+        assert_eq!(
+            stats.mixed_parts() + stats.duplicate_frames(),
+            mixed_frames + 1
+        );
+        assert_eq!(stats.restarts(), 0);
+        assert!(stats.time_to_complete().is_some());
+
+        let events = events.lock().unwrap();
+        assert_eq!(events[0], DecoderEvent::Started { fragment_count: 5 });
+        assert_eq!(
+            events[1],
+            DecoderEvent::FragmentsRecovered {
+                recovered: 1,
+                fragment_count: 5
+            }
+        );
+        assert!(matches!(events[2], DecoderEvent::Rejected(_)));
+        assert_eq!(events.last(), Some(&DecoderEvent::Completed));
+        let recovered: Vec<usize> = events
+            .iter()
+            .filter_map(|event| match event {
+                DecoderEvent::FragmentsRecovered { recovered, .. } => {
+                    Some(*recovered)
+                }
+                _ => None,
+            })
+            .collect();
+        assert!(recovered.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(recovered.last(), Some(&5));
+
+        decoder.reset();
+        assert_eq!(decoder.stats().time_to_complete(), None);
+        assert_eq!(decoder.stats().frames_received(), mixed_frames + 4);
+    }
+
+    #[test]
+    fn test_multipart_decoder_errors() {
+        let mut decoder = MultipartDecoder::new();
+        assert!(matches!(
+            decoder.receive("ur:test/lsadaoaxjygonesw"),
+            Err(Error::NotMultiPart)
+        ));
+
+        // The part inside claims to be 1-9, not 2-9.
+        let mut decoder = MultipartDecoder::new();
+        assert!(matches!(
+            decoder.receive(
+                "ur:bytes/2-9/lpadascfadaxcywenbpljkhdcahkadaemejtswhhylkepmykhhtsytsnoyoyaxaedsuttydmmhhpktpmsrjtdkgslpgh"
+            ),
+            Err(Error::InvalidSequence)
+        ));
+
+        // The rejected part did not fix the type of the message.
+        decoder
+            .receive(
+                "ur:other/1-9/lpadascfadaxcywenbpljkhdcahkadaemejtswhhylkepmykhhtsytsnoyoyaxaedsuttydmmhhpktpmsrjtdkgslpgh",
+            )
+            .unwrap();
     }
 }
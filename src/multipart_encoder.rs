@@ -1,25 +1,255 @@
-use crate::{Result, UR};
+import_stdlib!();
 
-pub struct MultipartEncoder<'a> {
-    encoder: ur::Encoder<'a>,
+use core::iter;
+
+use crate::{
+    Case, EmissionStrategy, FountainEncoder, FountainPart, RawUR, Result, UR,
+    URType, bytewords, wipe::Wiped,
+};
+
+pub struct MultipartEncoder {
+    ur_type: URType,
+    encoder: FountainEncoder,
+    strategy: EmissionStrategy,
+    emitted: u64,
 }
 
-impl<'a> MultipartEncoder<'a> {
-    pub fn new(ur: &'a UR, max_fragment_len: usize) -> Result<Self> {
+impl MultipartEncoder {
+    pub fn new(ur: &UR, max_fragment_len: usize) -> Result<Self> {
         Ok(Self {
-            encoder: ur::Encoder::new(
-                &ur.cbor().to_cbor_data(),
+            ur_type: ur.ur_type().clone(),
+            encoder: FountainEncoder::new(
+                &*Wiped(ur.cbor_ref().to_cbor_data()),
                 max_fragment_len,
-                ur.ur_type_str(),
             )?,
+            strategy: EmissionStrategy::default(),
+            emitted: 0,
+        })
+    }
+
+    /// Creates an encoder for a raw UR, fragmenting its stored bytes without
+    /// re-encoding the CBOR.
+    pub fn from_raw(raw: &RawUR, max_fragment_len: usize) -> Result<Self> {
+        Ok(Self {
+            ur_type: raw.ur_type().clone(),
+            encoder: FountainEncoder::new(raw.cbor_data(), max_fragment_len)?,
+            strategy: EmissionStrategy::default(),
+            emitted: 0,
         })
     }
 
-    pub fn next_part(&mut self) -> Result<String> {
-        Ok(self.encoder.next_part()?)
+    /// Emits parts in the order given by `strategy`, starting again from its
+    /// first part.
+    pub fn with_strategy(mut self, strategy: EmissionStrategy) -> Self {
+        self.strategy = strategy;
+        self.emitted = 0;
+        self
+    }
+
+    /// Returns the order in which parts are emitted.
+    pub fn strategy(&self) -> EmissionStrategy { self.strategy }
+
+    pub fn next_part(&mut self) -> Result<String> { Ok(self.emit()) }
+
+    /// Returns the part with the given sequence number, pure or mixed,
+    /// without generating the parts before it or affecting the encoder's
+    /// position. The same message and maximum fragment length always give the
+    /// same part, so frames can be printed or served statelessly.
+    ///
+    /// Returns [`Error::InvalidSequence`](crate::Error::InvalidSequence) if
+    /// `seq_num` is zero, as sequence numbers start at 1.
+    pub fn part(&self, seq_num: u32) -> Result<String> {
+        Ok(self.part_string(&self.encoder.part(seq_num)?))
+    }
+
+    /// Returns the first [`parts_count`](Self::parts_count) parts, which carry
+    /// the fragments in order without mixing. They are the smallest set of
+    /// parts from which a receiver can reassemble the message, and need no
+    /// fountain decoding.
+    ///
+    /// The encoder's position is unaffected.
+    pub fn pure_parts(&self) -> Vec<String> {
+        (1..=self.encoder.seq_len() as u32)
+            .map(|seq_num| self.part_string(&self.encoder.make_part(seq_num)))
+            .collect()
+    }
+
+    /// Bounds the endless sequence of parts to `cycles` times
+    /// [`parts_count`](Self::parts_count) parts from the current position.
+    ///
+    /// A count too large for `usize` leaves the sequence effectively endless.
+    pub fn cycles(self, cycles: usize) -> iter::Take<Self> {
+        let count = cycles.saturating_mul(self.parts_count());
+        self.take(count)
     }
 
-    pub fn current_index(&self) -> usize { self.encoder.current_index() }
+    fn emit(&mut self) -> String {
+        let seq_num = self.strategy.seq_num(self.emitted, self.parts_count());
+        self.emitted += 1;
+        let part = self.encoder.emit(seq_num);
+        self.part_string(&part)
+    }
+
+    fn part_string(&self, part: &FountainPart) -> String {
+        let mut s =
+            format!("ur:{}/{}/", self.ur_type.string(), part.sequence_id());
+        bytewords::write_minimal(&mut s, &Wiped(part.cbor_data()), Case::Lower)
+            .unwrap();
+        s
+    }
+
+    pub fn current_index(&self) -> usize { self.encoder.seq_num() as usize }
+
+    pub fn parts_count(&self) -> usize { self.encoder.seq_len() }
+
+    /// Returns the underlying fountain encoder.
+    pub fn fountain_encoder(&self) -> &FountainEncoder { &self.encoder }
+}
+
+/// Emits parts endlessly, as [`next_part`](MultipartEncoder::next_part) does.
+/// Use [`cycles`](MultipartEncoder::cycles) or [`Iterator::take`] to stop.
+impl Iterator for MultipartEncoder {
+    type Item = String;
 
-    pub fn parts_count(&self) -> usize { self.encoder.fragment_count() }
+    fn next(&mut self) -> Option<String> { Some(self.emit()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use dcbor::prelude::*;
+
+    use super::*;
+    use crate::{Error, MultipartDecoder, xoshiro256::make_message};
+
+    fn encoder() -> MultipartEncoder {
+        let message = "The only thing we have to fear is fear itself.";
+        let ur =
+            UR::new("bytes", CBOR::to_byte_string(message.as_bytes())).unwrap();
+        MultipartEncoder::new(&ur, 10).unwrap()
+    }
+
+    #[test]
+    fn test_multipart_encoder() {
+        let message = make_message("Wolf", 256);
+        let ur = UR::new("bytes", CBOR::to_byte_string(message)).unwrap();
+        let mut encoder = MultipartEncoder::new(&ur, 30).unwrap();
+        assert_eq!(encoder.parts_count(), 9);
+        let expected = [
+            "ur:bytes/1-9/lpadascfadaxcywenbpljkhdcahkadaemejtswhhylkepmykhhtsytsnoyoyaxaedsuttydmmhhpktpmsrjtdkgslpgh",
+            "ur:bytes/2-9/lpaoascfadaxcywenbpljkhdcagwdpfnsboxgwlbaawzuefywkdplrsrjynbvygabwjldapfcsgmghhkhstlrdcxaefz",
+            "ur:bytes/3-9/lpaxascfadaxcywenbpljkhdcahelbknlkuejnbadmssfhfrdpsbiegecpasvssovlgeykssjykklronvsjksopdzmol",
+            "ur:bytes/4-9/lpaaascfadaxcywenbpljkhdcasotkhemthydawydtaxneurlkosgwcekonertkbrlwmplssjtammdplolsbrdzcrtas",
+            "ur:bytes/5-9/lpahascfadaxcywenbpljkhdcatbbdfmssrkzmcwnezelennjpfzbgmuktrhtejscktelgfpdlrkfyfwdajldejokbwf",
+            "ur:bytes/6-9/lpamascfadaxcywenbpljkhdcackjlhkhybssklbwefectpfnbbectrljectpavyrolkzczcpkmwidmwoxkilghdsowp",
+            "ur:bytes/7-9/lpatascfadaxcywenbpljkhdcavszmwnjkwtclrtvaynhpahrtoxmwvwatmedibkaegdosftvandiodagdhthtrlnnhy",
+            "ur:bytes/8-9/lpayascfadaxcywenbpljkhdcadmsponkkbbhgsoltjntegepmttmoonftnbuoiyrehfrtsabzsttorodklubbuyaetk",
+            "ur:bytes/9-9/lpasascfadaxcywenbpljkhdcajskecpmdckihdyhphfotjojtfmlnwmadspaxrkytbztpbauotbgtgtaeaevtgavtny",
+            "ur:bytes/10-9/lpbkascfadaxcywenbpljkhdcahkadaemejtswhhylkepmykhhtsytsnoyoyaxaedsuttydmmhhpktpmsrjtwdkiplzs",
+            "ur:bytes/11-9/lpbdascfadaxcywenbpljkhdcahelbknlkuejnbadmssfhfrdpsbiegecpasvssovlgeykssjykklronvsjkvetiiapk",
+            "ur:bytes/12-9/lpbnascfadaxcywenbpljkhdcarllaluzmdmgstospeyiefmwejlwtpedamktksrvlcygmzemovovllarodtmtbnptrs",
+            "ur:bytes/13-9/lpbtascfadaxcywenbpljkhdcamtkgtpknghchchyketwsvwgwfdhpgmgtylctotzopdrpayoschcmhplffziachrfgd",
+            "ur:bytes/14-9/lpbaascfadaxcywenbpljkhdcapazewnvonnvdnsbyleynwtnsjkjndeoldydkbkdslgjkbbkortbelomueekgvstegt",
+            "ur:bytes/15-9/lpbsascfadaxcywenbpljkhdcaynmhpddpzmversbdqdfyrehnqzlugmjzmnmtwmrouohtstgsbsahpawkditkckynwt",
+            "ur:bytes/16-9/lpbeascfadaxcywenbpljkhdcawygekobamwtlihsnpalnsghenskkiynthdzotsimtojetprsttmukirlrsbtamjtpd",
+            "ur:bytes/17-9/lpbyascfadaxcywenbpljkhdcamklgftaxykpewyrtqzhydntpnytyisincxmhtbceaykolduortotiaiaiafhiaoyce",
+            "ur:bytes/18-9/lpbgascfadaxcywenbpljkhdcahkadaemejtswhhylkepmykhhtsytsnoyoyaxaedsuttydmmhhpktpmsrjtntwkbkwy",
+            "ur:bytes/19-9/lpbwascfadaxcywenbpljkhdcadekicpaajootjzpsdrbalpeywllbdsnbinaerkurspbncxgslgftvtsrjtksplcpeo",
+            "ur:bytes/20-9/lpbbascfadaxcywenbpljkhdcayapmrleeleaxpasfrtrdkncffwjyjzgyetdmlewtkpktgllepfrltataztksmhkbot",
+        ];
+        for (index, e) in expected.into_iter().enumerate() {
+            assert_eq!(encoder.current_index(), index);
+            assert_eq!(encoder.next_part().unwrap(), e);
+        }
+    }
+
+    #[test]
+    fn test_iterator() {
+        let mut manual = encoder();
+        let expected: Vec<String> =
+            (0..12).map(|_| manual.next_part().unwrap()).collect();
+        let parts: Vec<String> = encoder().take(12).collect();
+        assert_eq!(parts, expected);
+
+        let parts = encoder().cycles(usize::MAX);
+        assert_eq!(parts.size_hint().1, Some(usize::MAX));
+
+        let mut encoder = encoder();
+        assert_eq!(encoder.parts_count(), 5);
+        encoder.next_part().unwrap();
+        let parts: Vec<String> = encoder.cycles(2).collect();
+        assert_eq!(parts, expected[1..11]);
+    }
+
+    #[test]
+    fn test_pure_parts() {
+        let mut encoder = encoder();
+        encoder.next_part().unwrap();
+        let parts = encoder.pure_parts();
+        assert_eq!(parts.len(), 5);
+        assert!(parts[0].starts_with("ur:bytes/1-5/"));
+        assert!(parts[4].starts_with("ur:bytes/5-5/"));
+        assert_eq!(encoder.current_index(), 1);
+        assert_eq!(parts[1], encoder.next_part().unwrap());
+
+        let mut decoder = MultipartDecoder::new();
+        for part in &parts {
+            decoder.receive(part).unwrap();
+        }
+        assert!(decoder.is_complete());
+    }
+
+    #[test]
+    fn test_part() {
+        let sequential: Vec<String> = encoder().take(1000).collect();
+        let encoder = encoder();
+        assert_eq!(encoder.part(1000).unwrap(), sequential[999]);
+        assert_eq!(encoder.part(3).unwrap(), sequential[2]);
+        assert_eq!(encoder.current_index(), 0);
+        assert!(matches!(encoder.part(0), Err(Error::InvalidSequence)));
+
+        // Mixed parts alone, chosen at random, still complete the message.
+        let mut decoder = MultipartDecoder::new();
+        for seq_num in (1_000_000..).step_by(7919) {
+            decoder.receive(&encoder.part(seq_num).unwrap()).unwrap();
+            if decoder.is_complete() {
+                break;
+            }
+        }
+        assert!(decoder.message().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_strategies() {
+        use EmissionStrategy::*;
+
+        let standard: Vec<String> = encoder().take(20).collect();
+        for strategy in [
+            Standard,
+            StartAt(3),
+            StartAt(1000),
+            RandomStart(7),
+            PureOnly,
+            Redundancy(0.4),
+        ] {
+            let mut encoder = encoder().with_strategy(strategy);
+            assert_eq!(encoder.strategy(), strategy);
+            let mut decoder = MultipartDecoder::new();
+            for _ in 0..100 {
+                let part = encoder.next_part().unwrap();
+                let seq_num = encoder.current_index();
+                if let Some(expected) = standard.get(seq_num - 1) {
+                    assert_eq!(&part, expected);
+                }
+                decoder.receive(&part).unwrap();
+                if decoder.is_complete() {
+                    break;
+                }
+            }
+            assert!(decoder.is_complete(), "{strategy:?} did not complete");
+        }
+
+        let mut encoder = encoder().with_strategy(StartAt(4));
+        assert!(encoder.next_part().unwrap().starts_with("ur:bytes/4-5/"));
+        assert_eq!(encoder.current_index(), 4);
+    }
 }
@@ -0,0 +1,187 @@
+import_stdlib!();
+
+use crate::Error;
+
+/// The component of a UR string in which a parse error was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum URSegment {
+    /// The `ur:` scheme prefix.
+    Scheme,
+    /// The UR type, e.g. `seed` in `ur:seed/...`.
+    Type,
+    /// The `n-m` sequence component of a multipart UR.
+    Sequence,
+    /// The bytewords payload. `word_index` is the zero-based index of the
+    /// offending two-letter word.
+    Payload { word_index: usize },
+}
+
+impl fmt::Display for URSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            URSegment::Scheme => write!(f, "scheme"),
+            URSegment::Type => write!(f, "type"),
+            URSegment::Sequence => write!(f, "sequence"),
+            URSegment::Payload { word_index } => {
+                write!(f, "payload word {}", word_index)
+            }
+        }
+    }
+}
+
+/// An error locating the position of a problem within a UR string.
+///
+/// Offsets are byte offsets into the input string as passed to the parser.
+#[derive(Debug, thiserror::Error)]
+#[error("{reason} at byte {offset} ({segment})")]
+pub struct ParseError {
+    offset: usize,
+    len: usize,
+    segment: URSegment,
+    reason: Box<Error>,
+}
+
+impl ParseError {
+    pub(crate) fn new(
+        offset: usize,
+        len: usize,
+        segment: URSegment,
+        reason: Error,
+    ) -> Self {
+        Self { offset, len, segment, reason: Box::new(reason) }
+    }
+
+    /// Returns the byte offset of the start of the offending span.
+    pub fn offset(&self) -> usize { self.offset }
+
+    /// Returns the length in bytes of the offending span.
+    pub fn len(&self) -> usize { self.len }
+
+    /// Returns `true` if the offending span is empty, as when input ends
+    /// before an expected component.
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Returns the segment of the UR string in which the error was found.
+    pub fn segment(&self) -> URSegment { self.segment }
+
+    /// Returns the underlying reason for the error.
+    pub fn reason(&self) -> &Error { &self.reason }
+
+    /// Renders the input with a caret underline beneath the offending span,
+    /// in the style of a compiler diagnostic.
+    ///
+    /// ```text
+    /// error: invalid UR type
+    ///   |
+    ///   | ur:te_st/lsadaoaxjygonesw
+    ///   |      ^ type
+    /// ```
+    pub fn render(&self, input: &str) -> String {
+        let start = clamp_to_char_boundary(input, self.offset);
+        let end = clamp_to_char_boundary(input, self.offset + self.len);
+        let column = input[..start].chars().count();
+        let width = input[start..end].chars().count().max(1);
+        format!(
+            "error: {}\n  |\n  | {}\n  | {}{} {}",
+            self.reason,
+            input,
+            " ".repeat(column),
+            "^".repeat(width),
+            self.segment
+        )
+    }
+}
+
+fn clamp_to_char_boundary(s: &str, mut index: usize) -> usize {
+    if index >= s.len() {
+        return s.len();
+    }
+    while !s.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, UR, URSegment};
+
+    fn parse_error(input: &str) -> crate::ParseError {
+        match UR::from_ur_string(input) {
+            Err(Error::Parse(err)) => err,
+            other => panic!("expected parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_scheme_error() {
+        let err = parse_error("xr:test/lsadaoaxjygonesw");
+        assert_eq!(err.segment(), URSegment::Scheme);
+        assert_eq!(err.offset(), 0);
+        assert!(matches!(err.reason(), Error::InvalidScheme));
+    }
+
+    #[test]
+    fn test_type_error() {
+        let input = "ur:te_st/lsadaoaxjygonesw";
+        let err = parse_error(input);
+        assert_eq!(err.segment(), URSegment::Type);
+        assert_eq!(err.offset(), 5);
+        assert_eq!(err.len(), 1);
+        assert!(matches!(err.reason(), Error::InvalidType));
+        assert_eq!(
+            err.render(input),
+            "error: invalid UR type\n  |\n  | ur:te_st/lsadaoaxjygonesw\n  |      ^ type"
+        );
+    }
+
+    #[test]
+    fn test_type_hyphen_error() {
+        let err = parse_error("ur:te--st/lsadaoaxjygonesw");
+        assert_eq!(err.segment(), URSegment::Type);
+        assert_eq!((err.offset(), err.len()), (6, 1));
+        assert!(matches!(err.reason(), Error::InvalidTypeHyphen));
+
+        let err = parse_error("ur:/lsadaoaxjygonesw");
+        assert_eq!((err.offset(), err.len()), (3, 0));
+        assert!(matches!(err.reason(), Error::TypeUnspecified));
+    }
+
+    #[test]
+    fn test_type_unspecified() {
+        let err = parse_error("ur:test");
+        assert_eq!(err.segment(), URSegment::Type);
+        assert_eq!((err.offset(), err.len()), (3, 4));
+        assert!(matches!(err.reason(), Error::TypeUnspecified));
+    }
+
+    #[test]
+    fn test_sequence_error() {
+        let err = parse_error("ur:test/1-x/lsadaoaxjygonesw");
+        assert_eq!(err.segment(), URSegment::Sequence);
+        assert_eq!((err.offset(), err.len()), (8, 3));
+        assert!(matches!(err.reason(), Error::InvalidSequence));
+
+        let err = parse_error("ur:test/1-3/lsadaoaxjygonesw");
+        assert_eq!(err.segment(), URSegment::Sequence);
+        assert!(matches!(err.reason(), Error::NotSinglePart));
+    }
+
+    #[test]
+    fn test_payload_errors() {
+        // "xx" is not a minimal byteword.
+        let err = parse_error("ur:test/lsadxxaxjygonesw");
+        assert_eq!(err.segment(), URSegment::Payload { word_index: 2 });
+        assert_eq!((err.offset(), err.len()), (12, 2));
+
+        // Odd-length payload.
+        let err = parse_error("ur:test/lsadaoaxjygones");
+        assert_eq!(err.segment(), URSegment::Payload { word_index: 7 });
+        assert_eq!((err.offset(), err.len()), (22, 1));
+
+        // Corrupted checksum points at the final four words.
+        let err = parse_error("ur:test/lsadaoaxjygoneso");
+        assert_eq!(err.segment(), URSegment::Payload { word_index: 4 });
+        assert_eq!((err.offset(), err.len()), (16, 8));
+    }
+}
@@ -0,0 +1,103 @@
+import_stdlib!();
+
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_core::Stream;
+use tokio::time::{Interval, MissedTickBehavior, interval};
+
+use crate::MultipartEncoder;
+
+/// An endless [`Stream`] of multipart UR parts, one per frame, for driving an
+/// animated QR code.
+///
+/// The first part is ready immediately and each later part one frame interval
+/// after the previous one. Frames missed because the consumer fell behind are
+/// skipped rather than emitted in a burst.
+pub struct PartStream {
+    encoder: MultipartEncoder,
+    interval: Interval,
+}
+
+impl PartStream {
+    /// Returns the underlying encoder.
+    pub fn encoder(&self) -> &MultipartEncoder { &self.encoder }
+
+    /// Stops the stream, returning the encoder at its current position.
+    pub fn into_encoder(self) -> MultipartEncoder { self.encoder }
+}
+
+impl Stream for PartStream {
+    type Item = String;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<String>> {
+        let this = &mut *self;
+        match this.interval.poll_tick(cx) {
+            Poll::Ready(_) => Poll::Ready(this.encoder.next()),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl MultipartEncoder {
+    /// Emits a part every `frame_interval`, starting immediately.
+    ///
+    /// Must be called within a Tokio runtime with the time driver enabled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame_interval` is zero.
+    pub fn into_stream(self, frame_interval: Duration) -> PartStream {
+        assert!(!frame_interval.is_zero(), "frame interval must be positive");
+        let mut interval = interval(frame_interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        PartStream { encoder: self, interval }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::future::poll_fn;
+
+    use dcbor::prelude::*;
+    use tokio::time::Instant;
+
+    use super::*;
+    use crate::UR;
+
+    async fn next(stream: &mut PartStream) -> Option<String> {
+        poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)).await
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_part_stream() {
+        let message = "The only thing we have to fear is fear itself.";
+        let ur =
+            UR::new("bytes", CBOR::to_byte_string(message.as_bytes())).unwrap();
+        let expected: Vec<String> =
+            MultipartEncoder::new(&ur, 10).unwrap().take(3).collect();
+
+        let encoder = MultipartEncoder::new(&ur, 10).unwrap();
+        let mut stream = encoder.into_stream(Duration::from_millis(100));
+        let start = Instant::now();
+        for (i, part) in expected.iter().enumerate() {
+            assert_eq!(next(&mut stream).await.as_ref(), Some(part));
+            assert_eq!(start.elapsed(), Duration::from_millis(100 * i as u64));
+        }
+        assert_eq!(stream.into_encoder().current_index(), 3);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "frame interval must be positive")]
+    async fn test_zero_frame_interval() {
+        let ur = UR::new("bytes", CBOR::to_byte_string([1, 2, 3])).unwrap();
+        let encoder = MultipartEncoder::new(&ur, 10).unwrap();
+        encoder.into_stream(Duration::ZERO);
+    }
+}
@@ -1,6 +1,7 @@
 pub use dcbor::prelude::*;
 
 pub use crate::{
-    Error as URError, MultipartDecoder, MultipartEncoder, Result as URResult,
-    UR, URCodable, URDecodable, UREncodable, bytewords,
+    Error as URError, MultipartDecoder, MultipartEncoder, RawUR,
+    Result as URResult, TypedUR, UR, URCodable, URDecodable, URDispatcher,
+    UREncodable, bytewords,
 };
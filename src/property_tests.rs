@@ -0,0 +1,167 @@
+//! Property tests over the functions that parse untrusted scanned input.
+
+use dcbor::prelude::*;
+use proptest::prelude::*;
+
+use crate::{
+    Error, MultipartDecoder, MultipartEncoder, UR,
+    bytewords::{self, BYTEWORDS, Style},
+};
+
+fn style() -> impl Strategy<Value = Style> {
+    prop_oneof![
+        Just(Style::Standard),
+        Just(Style::Uri),
+        Just(Style::Minimal)
+    ]
+}
+
+/// Strings made mostly of the characters that appear in UR strings, so that
+/// parsing gets past the scheme and type more often than with arbitrary text.
+fn ur_like_string() -> impl Strategy<Value = String> {
+    "(ur:|UR:)?[a-zA-Z0-9/:-]{0,200}"
+}
+
+/// Encodes the message as multipart UR strings, emitting `extra` parts plus
+/// several times as many parts as there are fragments.
+fn multipart_strings(
+    message: &[u8],
+    max_fragment_len: usize,
+    extra: usize,
+) -> (UR, Vec<String>) {
+    let ur = UR::new("bytes", CBOR::to_byte_string(message)).unwrap();
+    let mut encoder = MultipartEncoder::new(&ur, max_fragment_len).unwrap();
+    let count = extra + encoder.parts_count() * 4 + 10;
+    let parts = (0..count).map(|_| encoder.next_part().unwrap()).collect();
+    (ur, parts)
+}
+
+proptest! {
+    #[test]
+    fn bytewords_round_trip(
+        data in prop::collection::vec(any::<u8>(), 0..200),
+        style in style(),
+    ) {
+        let encoded = bytewords::encode(&data, style);
+        prop_assert_eq!(bytewords::decode(&encoded, style).unwrap(), data);
+    }
+
+    #[test]
+    fn bytewords_decode_does_not_panic(input in ".{0,200}", style in style()) {
+        let _ = bytewords::decode(&input, style);
+    }
+
+    #[test]
+    fn canonicalize_byteword_finds_bytewords(token in ".{0,6}") {
+        if let Some(word) = bytewords::canonicalize_byteword(&token) {
+            prop_assert!(BYTEWORDS.contains(&word.as_str()));
+            prop_assert_eq!(
+                bytewords::canonicalize_byteword(&word),
+                Some(word.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn ur_round_trip(data in prop::collection::vec(any::<u8>(), 0..200)) {
+        let ur = UR::new("bytes", CBOR::to_byte_string(&data)).unwrap();
+        prop_assert_eq!(UR::from_ur_string(ur.string()).unwrap(), ur.clone());
+        prop_assert_eq!(UR::from_ur_string(ur.qr_string()).unwrap(), ur);
+    }
+
+    #[test]
+    fn ur_parse_does_not_panic(input in ur_like_string()) {
+        let _ = UR::from_ur_string(input);
+    }
+
+    #[test]
+    fn ur_payload_does_not_panic(
+        data in prop::collection::vec(any::<u8>(), 0..200),
+    ) {
+        let ur_string = format!(
+            "ur:test/{}",
+            bytewords::encode(&data, Style::Minimal)
+        );
+        let _ = UR::from_ur_string(ur_string);
+    }
+
+    #[test]
+    fn multipart_round_trip(
+        message in prop::collection::vec(any::<u8>(), 1..300),
+        max_fragment_len in 1usize..100,
+        keep in prop::collection::vec(any::<bool>(), 0..50),
+    ) {
+        // Enough parts follow those that `keep` may drop to finish decoding.
+        let (ur, parts) =
+            multipart_strings(&message, max_fragment_len, keep.len());
+        let mut decoder = MultipartDecoder::new();
+        let kept = parts
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| keep.get(*i).copied().unwrap_or(true));
+        for (_, part) in kept {
+            decoder.receive(part).unwrap();
+            if decoder.is_complete() {
+                break;
+            }
+        }
+        prop_assert_eq!(decoder.message().unwrap(), Some(ur));
+    }
+
+    #[test]
+    fn multipart_decoder_never_accepts_a_corrupted_message(
+        message in prop::collection::vec(any::<u8>(), 1..300),
+        max_fragment_len in 1usize..50,
+        corrupt in any::<prop::sample::Index>(),
+        bit in 0u8..8,
+    ) {
+        let (ur, parts) = multipart_strings(&message, max_fragment_len, 0);
+        let corrupt = corrupt.index(parts.len());
+        let mut decoder = MultipartDecoder::new();
+        for (i, part) in parts.iter().enumerate() {
+            let result = if i == corrupt {
+                // Corrupt the fragment data at the end of the part, then
+                // re-encode it with a valid bytewords checksum.
+                let mut cbor_data = crate::ur_parser::parse_ur(part)
+                    .unwrap()
+                    .payload;
+                let last = cbor_data.len() - 1;
+                cbor_data[last] ^= 1 << bit;
+                let (prefix, _) = part.rsplit_once('/').unwrap();
+                decoder.receive(&format!(
+                    "{prefix}/{}",
+                    bytewords::encode(&cbor_data, Style::Minimal)
+                ))
+            } else {
+                decoder.receive(part)
+            };
+            match result {
+                Ok(()) => {}
+                Err(Error::ChecksumMismatch) => {
+                    prop_assert!(decoder.is_complete());
+                    prop_assert!(decoder.message().is_err());
+                    return Ok(());
+                }
+                Err(err) => prop_assert!(false, "unexpected error {err}"),
+            }
+            if decoder.is_complete() {
+                break;
+            }
+        }
+        // A corrupted part that was not needed leaves the message intact.
+        if let Ok(Some(decoded)) = decoder.message() {
+            prop_assert_eq!(decoded, ur);
+        }
+    }
+
+    #[test]
+    fn multipart_decoder_does_not_panic(
+        inputs in prop::collection::vec(ur_like_string(), 0..10),
+    ) {
+        let mut decoder = MultipartDecoder::new();
+        for input in inputs {
+            let _ = decoder.receive(&input);
+        }
+        let _ = decoder.message();
+    }
+}
@@ -0,0 +1,77 @@
+import_stdlib!();
+
+use crate::xoshiro256::Xoshiro256;
+
+/// Walker's alias method for sampling indexes in proportion to their weights,
+/// matching the reference implementation so that fountain degrees agree.
+pub(crate) struct RandomSampler {
+    probs: Vec<f64>,
+    aliases: Vec<usize>,
+}
+
+impl RandomSampler {
+    /// Creates a sampler for the given weights, which must be non-negative
+    /// with a positive sum.
+    pub fn new(mut weights: Vec<f64>) -> Self {
+        assert!(
+            weights.iter().all(|&w| w >= 0.0),
+            "negative probability encountered"
+        );
+        let sum: f64 = weights.iter().sum();
+        assert!(sum > 0.0, "probabilities don't sum to a positive value");
+
+        let count = weights.len();
+        for w in &mut weights {
+            *w *= count as f64 / sum;
+        }
+
+        // Indexes are visited from the highest down, as the reference does.
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) =
+            (0..count).rev().partition(|&i| weights[i] < 1.0);
+
+        let mut probs = vec![0.0; count];
+        let mut aliases = vec![0; count];
+        while let (Some(&a), Some(&g)) = (small.last(), large.last()) {
+            small.pop();
+            large.pop();
+            probs[a] = weights[a];
+            aliases[a] = g;
+            weights[g] += weights[a] - 1.0;
+            if weights[g] < 1.0 {
+                small.push(g);
+            } else {
+                large.push(g);
+            }
+        }
+        for i in large.into_iter().chain(small) {
+            probs[i] = 1.0;
+        }
+
+        Self { probs, aliases }
+    }
+
+    /// Returns the next sampled index.
+    pub fn next(&self, rng: &mut Xoshiro256) -> usize {
+        let r1 = rng.next_double();
+        let r2 = rng.next_double();
+        let i = (self.probs.len() as f64 * r1) as usize;
+        if r2 < self.probs[i] {
+            i
+        } else {
+            self.aliases[i]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "negative probability encountered")]
+    fn test_negative_weights() { RandomSampler::new(vec![2.0, -1.0]); }
+
+    #[test]
+    #[should_panic(expected = "probabilities don't sum to a positive value")]
+    fn test_zero_weights() { RandomSampler::new(vec![0.0]); }
+}
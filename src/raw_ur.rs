@@ -0,0 +1,251 @@
+import_stdlib!();
+
+use dcbor::prelude::*;
+
+use crate::{
+    Case, DecodeMode, Error, ParseError, Result, UR, URSegment, URType,
+    conformance::decode_cbor,
+    ur::{Redacted, ur_string_len, write_ur},
+    ur_parser,
+    wipe::{Wipe, Wiped},
+};
+
+/// A UR that holds its payload as encoded CBOR bytes.
+///
+/// Unlike [`UR`], which stores a decoded `CBOR` value and re-serializes it
+/// whenever a string is produced, `RawUR` keeps the original bytes and only
+/// decodes them when [`cbor`](Self::cbor) is first called. This suits large
+/// payloads that are mostly passed through, such as multi-megabyte PSBTs.
+///
+/// As with [`UR`], the `Debug` output of a sensitive `RawUR` is redacted,
+/// while its `Display` output is the full UR string unless
+/// [`redacted`](Self::redacted). With the `zeroize` feature, its bytes are
+/// cleared when it is dropped.
+pub struct RawUR {
+    ur_type: URType,
+    data: Vec<u8>,
+    cbor: OnceLock<CBOR>,
+}
+
+impl RawUR {
+    /// Creates a new raw UR from the provided type and encoded CBOR. The
+    /// data is not checked until it is decoded.
+    pub fn new(
+        ur_type: impl TryInto<URType, Error = Error>,
+        cbor_data: impl Into<Vec<u8>>,
+    ) -> Result<RawUR> {
+        let ur_type = ur_type.try_into()?;
+        Ok(RawUR { ur_type, data: cbor_data.into(), cbor: OnceLock::new() })
+    }
+
+    /// Creates a new raw UR from the provided UR string, decoding the
+    /// bytewords but not the CBOR.
+    pub fn from_ur_string(ur_string: impl Into<String>) -> Result<RawUR> {
+        let mut ur_string = Wiped(ur_string.into());
+        ur_string.make_ascii_lowercase();
+        let parsed = ur_parser::parse_ur(&ur_string)?;
+        if let Some((seq_offset, seq_len)) = parsed.sequence_span {
+            return Err(Error::Parse(ParseError::new(
+                seq_offset,
+                seq_len,
+                URSegment::Sequence,
+                Error::NotSinglePart,
+            )));
+        }
+        Ok(RawUR {
+            ur_type: parsed.ur_type,
+            data: parsed.payload,
+            cbor: OnceLock::new(),
+        })
+    }
+
+    /// Returns the encoded CBOR payload.
+    pub fn cbor_data(&self) -> &[u8] { &self.data }
+
+    /// Returns the decoded CBOR payload, decoding and caching it on first
+    /// use. Payloads that are not valid dCBOR are rejected.
+    pub fn cbor(&self) -> Result<&CBOR> {
+        if let Some(cbor) = self.cbor.get() {
+            return Ok(cbor);
+        }
+        let (cbor, _) = decode_cbor(&self.data, DecodeMode::Strict)?;
+        Ok(self.cbor.get_or_init(|| cbor))
+    }
+
+    /// Decodes the payload into a [`UR`].
+    pub fn to_ur(&self) -> Result<UR> {
+        Ok(UR::from_parts(self.ur_type.clone(), self.cbor()?.clone()))
+    }
+
+    /// Consumes the raw UR and returns its type and encoded CBOR payload.
+    pub fn into_parts(mut self) -> (URType, Vec<u8>) {
+        (self.ur_type.clone(), core::mem::take(&mut self.data))
+    }
+
+    /// Returns the String representation of the UR, encoding the stored bytes
+    /// directly.
+    pub fn string(&self) -> String { self.string_in_case(Case::Lower) }
+
+    /// Returns the String representation of the UR in uppercase,
+    /// most-efficient for QR codes.
+    pub fn qr_string(&self) -> String { self.string_in_case(Case::Upper) }
+
+    fn string_in_case(&self, case: Case) -> String {
+        let mut s =
+            String::with_capacity(ur_string_len(&self.ur_type, &self.data));
+        write_ur(&mut s, &self.ur_type, &self.data, case).unwrap();
+        s
+    }
+
+    /// Writes the UR string to `w` in the given case without building an
+    /// intermediate `String`.
+    pub fn write_to(&self, w: &mut impl fmt::Write, case: Case) -> fmt::Result {
+        write_ur(w, &self.ur_type, &self.data, case)
+    }
+
+    /// Writes the UR string to `w` in the given case.
+    #[cfg(feature = "std")]
+    pub fn write_to_io(
+        &self,
+        w: &mut impl std::io::Write,
+        case: Case,
+    ) -> std::io::Result<()> {
+        crate::ur::write_ur_io(w, &self.ur_type, &self.data, case)
+    }
+
+    /// Returns the data representation of the UR in uppercase,
+    /// most-efficient for QR codes.
+    pub fn qr_data(&self) -> Vec<u8> { self.qr_string().into_bytes() }
+
+    pub fn ur_type(&self) -> &URType { &self.ur_type }
+
+    /// Returns the UR type.
+    pub fn ur_type_str(&self) -> &str { self.ur_type.string() }
+
+    /// Returns `true` if the UR's type [is sensitive](URType::is_sensitive).
+    pub fn is_sensitive(&self) -> bool { self.ur_type.is_sensitive() }
+
+    /// Returns a value whose `Display` output is the UR string or, if the
+    /// UR's type is sensitive, only its type, as `ur:seed/<redacted>`.
+    pub fn redacted(&self) -> impl fmt::Display + '_ {
+        Redacted { ur_type: &self.ur_type, value: self }
+    }
+}
+
+impl Clone for RawUR {
+    fn clone(&self) -> Self {
+        Self {
+            ur_type: self.ur_type.clone(),
+            data: self.data.clone(),
+            cbor: self.cbor.clone(),
+        }
+    }
+}
+
+impl Drop for RawUR {
+    fn drop(&mut self) { self.data.wipe(); }
+}
+
+impl fmt::Debug for RawUR {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("RawUR");
+        s.field("ur_type", &self.ur_type);
+        if self.ur_type.should_redact() {
+            s.field("data", &format_args!("<redacted>"));
+        } else {
+            s.field("data", &self.data);
+        }
+        s.finish()
+    }
+}
+
+impl PartialEq for RawUR {
+    fn eq(&self, other: &Self) -> bool {
+        self.ur_type == other.ur_type && self.data == other.data
+    }
+}
+
+impl From<&UR> for RawUR {
+    fn from(ur: &UR) -> Self {
+        let cbor = ur.cbor();
+        RawUR {
+            ur_type: ur.ur_type().clone(),
+            data: cbor.to_cbor_data(),
+            cbor: OnceLock::from(cbor),
+        }
+    }
+}
+
+impl From<UR> for RawUR {
+    fn from(ur: UR) -> Self {
+        let (ur_type, cbor) = ur.into_parts();
+        RawUR {
+            ur_type,
+            data: cbor.to_cbor_data(),
+            cbor: OnceLock::from(cbor),
+        }
+    }
+}
+
+impl TryFrom<RawUR> for UR {
+    type Error = Error;
+
+    fn try_from(raw: RawUR) -> Result<Self> { raw.to_ur() }
+}
+
+impl From<RawUR> for String {
+    fn from(raw: RawUR) -> Self { raw.string() }
+}
+
+impl TryFrom<String> for RawUR {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> { RawUR::from_ur_string(value) }
+}
+
+impl fmt::Display for RawUR {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_to(f, Case::Lower)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_ur() {
+        let raw = RawUR::from_ur_string("ur:test/lsadaoaxjygonesw").unwrap();
+        assert_eq!(raw.cbor_data(), &[0x83, 0x01, 0x02, 0x03]);
+        assert_eq!(raw.string(), "ur:test/lsadaoaxjygonesw");
+        assert_eq!(raw.cbor().unwrap(), &CBOR::from(vec![1, 2, 3]));
+
+        let ur = raw.to_ur().unwrap();
+        assert_eq!(RawUR::from(&ur), raw);
+    }
+
+    #[test]
+    fn test_raw_ur_lazy_decode() {
+        // Not valid CBOR, but the string round-trips without decoding it.
+        let raw = RawUR::new("test", vec![0x83, 0x01]).unwrap();
+        let ur_string = raw.string();
+        assert_eq!(RawUR::from_ur_string(ur_string).unwrap(), raw);
+        assert!(raw.cbor().is_err());
+        assert!(UR::try_from(raw).is_err());
+    }
+
+    #[test]
+    fn test_raw_ur_redaction() {
+        let raw = RawUR::new("seed", vec![0x41, 0xaa]).unwrap();
+        assert!(raw.is_sensitive());
+        assert_eq!(raw.to_string(), raw.string());
+        assert_eq!(
+            format!("{raw:?}"),
+            r#"RawUR { ur_type: URType("seed"), data: <redacted> }"#
+        );
+        assert_eq!(raw.redacted().to_string(), "ur:seed/<redacted>");
+        let plain = RawUR::new("test", vec![0x01]).unwrap();
+        assert_eq!(plain.redacted().to_string(), plain.string());
+        assert_eq!(raw.into_parts().1, [0x41, 0xaa]);
+    }
+}
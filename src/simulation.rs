@@ -0,0 +1,240 @@
+import_stdlib!();
+
+use crate::{
+    EmissionStrategy, MultipartDecoder, MultipartEncoder, RawUR, Result,
+    xoshiro256::Xoshiro256,
+};
+
+/// Simulates sending messages as animated QR codes over a lossy channel, to
+/// choose a maximum fragment length and frame rate, or to exercise the
+/// decoder offline.
+///
+/// Each trial encodes a pseudorandom message with a [`MultipartEncoder`] and
+/// feeds its frames to a [`MultipartDecoder`], dropping each frame with the
+/// given probability, until the message is recovered. Runs with the same
+/// parameters and seed give the same [`SimulationReport`].
+///
+/// ```
+/// use bc_ur::Simulation;
+///
+/// let report = Simulation::new(1000, 100)
+///     .with_loss(0.2)
+///     .with_trials(50)
+///     .run()?;
+/// assert_eq!(report.fragment_count(), 10);
+/// assert_eq!(report.failures(), 0);
+/// // The number of frames to display before 95% of receivers are done.
+/// let frames = report.percentile(0.95).unwrap();
+/// assert!(frames >= 10);
+/// # Ok::<(), bc_ur::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    message_len: usize,
+    max_fragment_len: usize,
+    loss: f64,
+    seed: u64,
+    trials: usize,
+    max_frames: Option<usize>,
+    strategy: EmissionStrategy,
+}
+
+impl Simulation {
+    /// Creates a simulation of 100 trials of messages of `message_len` bytes
+    /// sent in fragments of at most `max_fragment_len` bytes, with no loss.
+    pub fn new(message_len: usize, max_fragment_len: usize) -> Self {
+        Self {
+            message_len,
+            max_fragment_len,
+            loss: 0.0,
+            seed: 0,
+            trials: 100,
+            max_frames: None,
+            strategy: EmissionStrategy::default(),
+        }
+    }
+
+    /// Drops each frame with probability `loss`, clamped to `[0, 1]`.
+    pub fn with_loss(mut self, loss: f64) -> Self {
+        self.loss = loss.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Seeds the generator that chooses the messages and the frames lost.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Sets the number of messages to send.
+    pub fn with_trials(mut self, trials: usize) -> Self {
+        self.trials = trials;
+        self
+    }
+
+    /// Gives up on a message after `max_frames` frames. The default is 100
+    /// frames per fragment.
+    pub fn with_max_frames(mut self, max_frames: usize) -> Self {
+        self.max_frames = Some(max_frames);
+        self
+    }
+
+    /// Emits frames in the order given by `strategy`.
+    pub fn with_strategy(mut self, strategy: EmissionStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Runs the trials.
+    ///
+    /// Returns an error if the message length or maximum fragment length is
+    /// invalid, or if the decoder rejects a frame.
+    pub fn run(&self) -> Result<SimulationReport> {
+        let mut rng = Xoshiro256::from_data(&self.seed.to_be_bytes());
+        let mut frames_needed = Vec::with_capacity(self.trials);
+        let mut fragment_count = 0;
+        let mut failures = 0;
+        for _ in 0..self.trials {
+            let message = RawUR::new("bytes", rng.next_data(self.message_len))?;
+            let encoder =
+                MultipartEncoder::from_raw(&message, self.max_fragment_len)?
+                    .with_strategy(self.strategy);
+            fragment_count = encoder.parts_count();
+            let max_frames = self
+                .max_frames
+                .unwrap_or(fragment_count.saturating_mul(100));
+            let mut decoder = MultipartDecoder::new();
+            let mut frames = 0;
+            for part in encoder.take(max_frames) {
+                frames += 1;
+                if rng.next_double() < self.loss {
+                    continue;
+                }
+                decoder.receive(&part)?;
+                if decoder.is_complete() {
+                    break;
+                }
+            }
+            if decoder.is_complete() {
+                debug_assert_eq!(
+                    decoder.fountain_decoder().message()?,
+                    Some(message.cbor_data())
+                );
+                frames_needed.push(frames);
+            } else {
+                failures += 1;
+            }
+        }
+        frames_needed.sort_unstable();
+        Ok(SimulationReport { fragment_count, frames_needed, failures })
+    }
+}
+
+/// The outcome of a [`Simulation`]: how many frames were sent before each
+/// message was recovered, counting frames that were lost. Divide by the
+/// frame rate for the time a receiver takes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationReport {
+    fragment_count: usize,
+    frames_needed: Vec<usize>,
+    failures: usize,
+}
+
+impl SimulationReport {
+    /// Returns the number of fragments each message was split into.
+    pub fn fragment_count(&self) -> usize { self.fragment_count }
+
+    /// Returns the number of frames sent for each message that was
+    /// recovered, in ascending order.
+    pub fn frames_needed(&self) -> &[usize] { &self.frames_needed }
+
+    /// Returns the number of messages not recovered within the maximum
+    /// number of frames.
+    pub fn failures(&self) -> usize { self.failures }
+
+    /// Returns the fewest frames any message needed.
+    pub fn min(&self) -> Option<usize> { self.frames_needed.first().copied() }
+
+    /// Returns the most frames any recovered message needed.
+    pub fn max(&self) -> Option<usize> { self.frames_needed.last().copied() }
+
+    /// Returns the mean number of frames the recovered messages needed.
+    pub fn mean(&self) -> Option<f64> {
+        if self.frames_needed.is_empty() {
+            return None;
+        }
+        let total: usize = self.frames_needed.iter().sum();
+        Some(total as f64 / self.frames_needed.len() as f64)
+    }
+
+    /// Returns the number of frames within which the fraction `p` of the
+    /// recovered messages were recovered, by the nearest-rank method. For
+    /// example, `percentile(0.95)` is the 95th percentile.
+    pub fn percentile(&self, p: f64) -> Option<usize> {
+        let len = self.frames_needed.len();
+        if len == 0 {
+            return None;
+        }
+        let rank = (p.clamp(0.0, 1.0) * len as f64).ceil() as usize;
+        Some(self.frames_needed[rank.clamp(1, len) - 1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    #[test]
+    fn test_lossless() {
+        let report = Simulation::new(1000, 100).with_trials(10).run().unwrap();
+        assert_eq!(report.fragment_count(), 10);
+        assert_eq!(report.frames_needed(), [10; 10]);
+        assert_eq!(report.failures(), 0);
+        assert_eq!(report.mean(), Some(10.0));
+    }
+
+    #[test]
+    fn test_lossy() {
+        let simulation = Simulation::new(1000, 100).with_loss(0.3).with_seed(7);
+        let report = simulation.run().unwrap();
+        assert_eq!(report, simulation.run().unwrap());
+        assert_ne!(report, simulation.clone().with_seed(8).run().unwrap());
+        assert_eq!(report.frames_needed().len(), 100);
+        assert!(report.min().unwrap() >= 10);
+        assert!(report.mean().unwrap() > 10.0);
+        assert!(report.percentile(0.5) <= report.percentile(0.95));
+        assert_eq!(report.percentile(1.0), report.max());
+        assert_eq!(report.percentile(0.0), report.min());
+
+        // Starting mid-stream does not stop the message being recovered.
+        let report = simulation
+            .with_strategy(EmissionStrategy::RandomStart(1))
+            .run()
+            .unwrap();
+        assert_eq!(report.failures(), 0);
+    }
+
+    #[test]
+    fn test_failures() {
+        let report = Simulation::new(100, 10)
+            .with_loss(1.0)
+            .with_trials(3)
+            .with_max_frames(50)
+            .run()
+            .unwrap();
+        assert_eq!(report.failures(), 3);
+        assert!(report.frames_needed().is_empty());
+        assert_eq!(report.mean(), None);
+        assert_eq!(report.percentile(0.5), None);
+
+        assert!(matches!(
+            Simulation::new(0, 10).run(),
+            Err(Error::EmptyMessage)
+        ));
+        assert!(matches!(
+            Simulation::new(100, 0).run(),
+            Err(Error::InvalidFragmentLen)
+        ));
+    }
+}
@@ -0,0 +1,52 @@
+#![allow(unused_imports)]
+
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub(crate) mod with_std {
+    pub(crate) use std::{
+        borrow::{Cow, ToOwned},
+        boxed::Box,
+        collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+        fmt, format,
+        result::Result as StdResult,
+        str,
+        string::{String, ToString},
+        sync::{LazyLock, OnceLock, RwLock},
+        vec,
+        vec::Vec,
+    };
+}
+
+#[cfg(not(feature = "std"))]
+#[cfg(feature = "no_std")]
+#[doc(hidden)]
+pub(crate) mod without_std {
+    pub(crate) use alloc::{
+        borrow::{Cow, ToOwned},
+        boxed::Box,
+        collections::{BTreeMap, BTreeSet},
+        fmt, format, str,
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    };
+    // `OnceCell` is not `Sync`, so under `no_std` a `RawUR` cannot be
+    // shared between threads.
+    pub(crate) use core::{
+        cell::OnceCell as OnceLock, result::Result as StdResult,
+    };
+
+    pub(crate) use hashbrown::{HashMap, HashSet};
+    pub(crate) use spin::{Lazy as LazyLock, RwLock};
+}
+
+macro_rules! import_stdlib {
+    () => {
+        #[allow(unused_imports)]
+        #[cfg(feature = "std")]
+        use $crate::stdlib::with_std::*;
+        #[allow(unused_imports)]
+        #[cfg(not(feature = "std"))]
+        use $crate::stdlib::without_std::*;
+    };
+}
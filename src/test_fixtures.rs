@@ -0,0 +1,41 @@
+//! Types shared by the unit tests of several modules.
+
+use dcbor::prelude::*;
+
+/// A tagged unsigned integer.
+///
+/// Its tag, 40000, has no name, so its UR type is the one the global
+/// registry's [`KNOWN_UR_TYPES`](crate::KNOWN_UR_TYPES) assign to the tag:
+/// `known-value`. Tests using it need not register anything.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TaggedNumber(pub u64);
+
+impl TaggedNumber {
+    pub const UR_TYPE: &str = "known-value";
+}
+
+impl CBORTagged for TaggedNumber {
+    fn cbor_tags() -> Vec<Tag> { vec![Tag::with_value(40000)] }
+}
+
+impl From<TaggedNumber> for CBOR {
+    fn from(value: TaggedNumber) -> Self { value.tagged_cbor() }
+}
+
+impl CBORTaggedEncodable for TaggedNumber {
+    fn untagged_cbor(&self) -> CBOR { self.0.into() }
+}
+
+impl TryFrom<CBOR> for TaggedNumber {
+    type Error = dcbor::Error;
+
+    fn try_from(cbor: CBOR) -> dcbor::Result<Self> {
+        Self::from_tagged_cbor(cbor)
+    }
+}
+
+impl CBORTaggedDecodable for TaggedNumber {
+    fn from_untagged_cbor(cbor: CBOR) -> dcbor::Result<Self> {
+        Ok(Self(cbor.try_into()?))
+    }
+}
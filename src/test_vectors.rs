@@ -0,0 +1,245 @@
+//! Conformance tests for BCR-2020-005 and BCR-2020-012 driven by the vectors
+//! in `tests/vectors`. Every case records in its `source` field the test of
+//! the `ur` crate it was copied from; those vectors in turn come from the
+//! bc-ur and URKit reference implementations.
+
+use dcbor::prelude::*;
+use serde_json::Value;
+
+use crate::{
+    Error, FountainEncoder, FountainPart, MultipartDecoder, MultipartEncoder,
+    RawUR, UR,
+    bytewords::{self, Style},
+    fountain_utils::{choose_fragments, fragment_len, partition},
+    random_sampler::RandomSampler,
+    xoshiro256::{Xoshiro256, make_message},
+};
+
+macro_rules! vectors {
+    ($name:literal) => {
+        serde_json::from_str::<Value>(include_str!(concat!(
+            "../tests/vectors/",
+            $name,
+            ".json"
+        )))
+        .unwrap()
+    };
+}
+
+fn str(v: &Value) -> &str { v.as_str().unwrap() }
+
+fn uint(v: &Value) -> u64 { v.as_u64().unwrap() }
+
+fn array(v: &Value) -> &[Value] { v.as_array().unwrap() }
+
+fn uints(v: &Value) -> Vec<u64> { array(v).iter().map(uint).collect() }
+
+/// Generates the message described by a `{"seed", "len"}` object.
+fn make_vector_message(v: &Value) -> Vec<u8> {
+    make_message(str(&v["seed"]), uint(&v["len"]) as usize)
+}
+
+fn style(v: &Value) -> Style {
+    match str(v) {
+        "standard" => Style::Standard,
+        "uri" => Style::Uri,
+        "minimal" => Style::Minimal,
+        other => panic!("unknown bytewords style {other}"),
+    }
+}
+
+#[test]
+fn test_bytewords_vectors() {
+    let vectors = vectors!("bytewords");
+    for case in array(&vectors["valid"]) {
+        let data = hex::decode(str(&case["data"])).unwrap();
+        for (name, style) in [
+            ("standard", Style::Standard),
+            ("uri", Style::Uri),
+            ("minimal", Style::Minimal),
+        ] {
+            let encoded = str(&case[name]);
+            assert_eq!(bytewords::encode(&data, style), encoded);
+            assert_eq!(bytewords::decode(encoded, style).unwrap(), data);
+        }
+    }
+    for case in array(&vectors["invalid"]) {
+        let input = str(&case["input"]);
+        assert!(
+            bytewords::decode(input, style(&case["style"])).is_err(),
+            "{input:?} should be rejected: {}",
+            str(&case["reason"])
+        );
+    }
+}
+
+#[test]
+fn test_crc32_vectors() {
+    let vectors = vectors!("crc32");
+    for case in array(&vectors["cases"]) {
+        let checksum = bytewords::crc32(str(&case["utf8"]).as_bytes());
+        assert_eq!(format!("{checksum:08x}"), str(&case["crc32"]));
+    }
+}
+
+#[test]
+fn test_xoshiro_vectors() {
+    let vectors = vectors!("xoshiro");
+
+    for case in array(&vectors["next_u64_mod_100"]) {
+        let mut rng = match (case.get("seed"), case.get("seed_crc32")) {
+            (Some(seed), None) => Xoshiro256::from_data(str(seed).as_bytes()),
+            (None, Some(seed)) => {
+                let checksum = bytewords::crc32(str(seed).as_bytes());
+                Xoshiro256::from_data(&checksum.to_be_bytes())
+            }
+            _ => panic!("expected one seed"),
+        };
+        for e in uints(&case["values"]) {
+            assert_eq!(rng.next_u64() % 100, e);
+        }
+    }
+
+    let case = &vectors["next_int_1_10"];
+    let mut rng = Xoshiro256::from_data(str(&case["seed"]).as_bytes());
+    for e in uints(&case["values"]) {
+        assert_eq!(rng.next_int(1, 10), e);
+    }
+
+    let case = &vectors["shuffle"];
+    let mut rng = Xoshiro256::from_data(str(&case["seed"]).as_bytes());
+    let items = uints(&case["items"]);
+    for e in array(&case["results"]) {
+        assert_eq!(rng.shuffled(items.clone()), uints(e));
+    }
+
+    let case = &vectors["sampler"];
+    let mut rng = Xoshiro256::from_data(str(&case["seed"]).as_bytes());
+    let weights = array(&case["weights"]).iter().map(|w| w.as_f64().unwrap());
+    let sampler = RandomSampler::new(weights.collect());
+    for e in uints(&case["samples"]) {
+        assert_eq!(sampler.next(&mut rng) as u64, e);
+    }
+
+    let case = &vectors["choose_degree"];
+    let fragment_count = uint(&case["fragment_count"]) as usize;
+    for (nonce, e) in (1..).zip(uints(&case["degrees"])) {
+        let seed = format!("{}{nonce}", str(&case["seed_prefix"]));
+        let mut rng = Xoshiro256::from_data(seed.as_bytes());
+        assert_eq!(rng.choose_degree(fragment_count) as u64, e);
+    }
+}
+
+#[test]
+fn test_fountain_vectors() {
+    let vectors = vectors!("fountain");
+
+    let case = &vectors["partition"];
+    let message = make_vector_message(&case["message"]);
+    let max_fragment_len = uint(&case["max_fragment_len"]) as usize;
+    let fragments =
+        partition(&message, fragment_len(message.len(), max_fragment_len));
+    let expected: Vec<&str> =
+        array(&case["fragments"]).iter().map(str).collect();
+    let actual: Vec<String> = fragments.iter().map(hex::encode).collect();
+    assert_eq!(actual, expected);
+    let mut joined = fragments.concat();
+    joined.truncate(message.len());
+    assert_eq!(joined, message);
+
+    let case = &vectors["choose_fragments"];
+    let message = make_vector_message(&case["message"]);
+    let checksum = bytewords::crc32(&message);
+    let max_fragment_len = uint(&case["max_fragment_len"]) as usize;
+    let seq_len = message
+        .len()
+        .div_ceil(fragment_len(message.len(), max_fragment_len));
+    for (seq_num, e) in (1..).zip(array(&case["indexes"])) {
+        let mut indexes: Vec<u64> =
+            choose_fragments(seq_num, seq_len, checksum)
+                .into_iter()
+                .map(|index| index as u64)
+                .collect();
+        indexes.sort_unstable();
+        assert_eq!(indexes, uints(e));
+    }
+
+    let case = &vectors["encoder"];
+    let message = make_vector_message(&case["message"]);
+    let max_fragment_len = uint(&case["max_fragment_len"]) as usize;
+    let mut encoder = FountainEncoder::new(&message, max_fragment_len).unwrap();
+    assert_eq!(encoder.seq_len() as u64, uint(&case["seq_len"]));
+    assert_eq!(encoder.fragment_len() as u64, uint(&case["fragment_len"]));
+    assert_eq!(encoder.checksum() as u64, uint(&case["checksum"]));
+    for e in array(&case["parts"]) {
+        let part = encoder.next_part();
+        assert_eq!(part.seq_num() as u64, uint(&e["seq_num"]));
+        assert_eq!(hex::encode(part.data()), str(&e["data"]));
+        let cbor = part.cbor_data();
+        assert_eq!(hex::encode(&cbor), str(&e["cbor"]));
+        assert_eq!(FountainPart::from_cbor_data(&cbor).unwrap(), part);
+    }
+}
+
+#[test]
+fn test_ur_vectors() {
+    let vectors = vectors!("ur");
+
+    for case in array(&vectors["valid"]) {
+        let ur_string = str(&case["ur"]);
+        let cbor_data = hex::decode(str(&case["cbor"])).unwrap();
+
+        let ur = UR::from_ur_string(ur_string).unwrap();
+        assert_eq!(ur.ur_type_str(), str(&case["ur_type"]));
+        assert_eq!(ur.cbor().to_cbor_data(), cbor_data);
+
+        let cbor = CBOR::try_from_data(&cbor_data).unwrap();
+        let ur = UR::new(str(&case["ur_type"]), cbor).unwrap();
+        assert_eq!(ur.string(), ur_string);
+        assert_eq!(ur.qr_string(), ur_string.to_uppercase());
+    }
+
+    for case in array(&vectors["raw"]) {
+        let ur_string = str(&case["ur"]);
+        let raw = RawUR::from_ur_string(ur_string).unwrap();
+        assert_eq!(raw.ur_type_str(), str(&case["ur_type"]));
+        assert_eq!(hex::encode(raw.cbor_data()), str(&case["data"]));
+        assert_eq!(raw.string(), ur_string);
+    }
+
+    for case in array(&vectors["invalid"]) {
+        match UR::from_ur_string(str(&case["ur"])) {
+            Err(Error::Parse(err)) => {
+                assert_eq!(err.reason().to_string(), str(&case["reason"]));
+            }
+            other => panic!("expected a parse error, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_multipart_vectors() {
+    let vectors = vectors!("multipart");
+
+    let case = &vectors["encoder"];
+    let message = make_vector_message(&case["message"]);
+    let ur =
+        UR::new(str(&case["ur_type"]), CBOR::to_byte_string(&message)).unwrap();
+    let max_fragment_len = uint(&case["max_fragment_len"]) as usize;
+    let mut encoder = MultipartEncoder::new(&ur, max_fragment_len).unwrap();
+    let parts: Vec<&str> = array(&case["parts"]).iter().map(str).collect();
+    for e in &parts {
+        assert_eq!(encoder.next_part().unwrap(), *e);
+    }
+
+    for order in [parts.clone(), parts.iter().rev().copied().collect()] {
+        let mut decoder = MultipartDecoder::new();
+        for part in order {
+            decoder.receive(part).unwrap();
+            if decoder.is_complete() {
+                break;
+            }
+        }
+        assert_eq!(decoder.message().unwrap(), Some(ur.clone()));
+    }
+}
@@ -0,0 +1,141 @@
+import_stdlib!();
+
+use core::marker::PhantomData;
+
+use crate::{Error, Result, UR, URCodable, ur_type_registry::ur_type_for};
+
+/// A UR that is statically known to hold a value of type `T`.
+///
+/// A `TypedUR<T>` can only be constructed from a UR whose type matches `T`'s
+/// UR type, so APIs can accept "a UR of a seed" without re-checking the type.
+pub struct TypedUR<T> {
+    ur: UR,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: URCodable> TypedUR<T> {
+    /// Creates a typed UR by encoding the value.
+    pub fn new(value: &T) -> Self { Self::unchecked(value.ur()) }
+
+    /// Creates a typed UR from an untyped one, checking that the UR type
+    /// matches `T`.
+    pub fn from_ur(ur: UR) -> Result<Self> {
+        ur.check_type(ur_type_for::<T>()?)?;
+        Ok(Self::unchecked(ur))
+    }
+
+    /// Parses a UR string, checking that the UR type matches `T`.
+    pub fn from_ur_string(ur_string: impl Into<String>) -> Result<Self> {
+        Self::from_ur(UR::from_ur_string(ur_string)?)
+    }
+
+    /// Decodes the payload as a `T`. The UR type is not checked again.
+    pub fn decode(&self) -> dcbor::Result<T> {
+        T::from_untagged_cbor(self.ur.cbor())
+    }
+
+    /// Decodes the payload as a `T`, consuming the typed UR so that the
+    /// payload can be moved rather than copied.
+    pub fn into_value(self) -> dcbor::Result<T> {
+        T::from_untagged_cbor(self.ur.into_parts().1)
+    }
+}
+
+impl<T> TypedUR<T> {
+    fn unchecked(ur: UR) -> Self { Self { ur, _marker: PhantomData } }
+
+    /// Returns the underlying untyped UR.
+    pub fn ur(&self) -> &UR { &self.ur }
+
+    /// Consumes the typed UR and returns the underlying untyped UR.
+    pub fn into_ur(self) -> UR { self.ur }
+
+    /// Returns the String representation of the UR.
+    pub fn string(&self) -> String { self.ur.string() }
+
+    /// Returns a value whose `Display` output is the UR string or, if the
+    /// UR's type is sensitive, only its type, as `ur:seed/<redacted>`.
+    pub fn redacted(&self) -> impl fmt::Display + '_ { self.ur.redacted() }
+}
+
+impl<T> Clone for TypedUR<T> {
+    fn clone(&self) -> Self { Self::unchecked(self.ur.clone()) }
+}
+
+impl<T> PartialEq for TypedUR<T> {
+    fn eq(&self, other: &Self) -> bool { self.ur == other.ur }
+}
+
+impl<T> fmt::Debug for TypedUR<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TypedUR").field(&self.ur).finish()
+    }
+}
+
+impl<T> fmt::Display for TypedUR<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.ur, f)
+    }
+}
+
+impl<T> AsRef<UR> for TypedUR<T> {
+    fn as_ref(&self) -> &UR { &self.ur }
+}
+
+impl<T> From<TypedUR<T>> for UR {
+    fn from(typed: TypedUR<T>) -> Self { typed.ur }
+}
+
+impl<T> From<TypedUR<T>> for String {
+    fn from(typed: TypedUR<T>) -> Self { typed.string() }
+}
+
+impl<T: URCodable> TryFrom<UR> for TypedUR<T> {
+    type Error = Error;
+
+    fn try_from(ur: UR) -> Result<Self> { Self::from_ur(ur) }
+}
+
+impl<T: URCodable> TryFrom<String> for TypedUR<T> {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> { Self::from_ur_string(value) }
+}
+
+impl<T: URCodable> TryFrom<&str> for TypedUR<T> {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self> { Self::from_ur_string(value) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::TaggedNumber;
+
+    #[test]
+    fn test_typed_ur() {
+        let typed = TypedUR::new(&TaggedNumber(7));
+        assert_eq!(typed.ur().ur_type_str(), TaggedNumber::UR_TYPE);
+        assert_eq!(typed.decode().unwrap(), TaggedNumber(7));
+
+        let ur_string = typed.string();
+        let parsed: TypedUR<TaggedNumber> =
+            ur_string.as_str().try_into().unwrap();
+        assert_eq!(parsed, typed);
+
+        let ur: UR = parsed.into();
+        let typed = TypedUR::<TaggedNumber>::from_ur(ur).unwrap();
+        assert_eq!(typed.into_value().unwrap(), TaggedNumber(7));
+    }
+
+    #[test]
+    fn test_typed_ur_wrong_type() {
+        let ur = UR::from_ur_string("ur:test/lsadaoaxjygonesw").unwrap();
+        assert!(matches!(
+            TypedUR::<TaggedNumber>::from_ur(ur),
+            Err(Error::UnexpectedType(expected, found))
+                if expected == TaggedNumber::UR_TYPE && found == "test"
+        ));
+    }
+}
@@ -1,10 +1,31 @@
+import_stdlib!();
+
 use dcbor::prelude::*;
-use ur::decode;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    DecodeMode, Error, Nonconformance, RawUR, Result, URType, bytewords,
+    conformance::decode_cbor, ur_type_registry::RegistryTags, wipe::Wiped,
+};
 
-use crate::{Error, Result, URType};
+/// The letter case in which a UR string is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Case {
+    /// The canonical lowercase form, e.g. `ur:test/lsadaoaxjygonesw`.
+    #[default]
+    Lower,
+    /// The uppercase form, most efficient for QR codes.
+    Upper,
+}
 
 /// A Uniform Resource (UR) is a URI-encoded CBOR object.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// The `Debug` output of a UR whose type
+/// [is sensitive](URType::is_sensitive), such as a seed, is redacted. Its
+/// `Display` output is the full UR string, as returned by
+/// [`string`](Self::string); use [`redacted`](Self::redacted) to display a
+/// UR that may be sensitive, as in logs.
+#[derive(Clone, PartialEq)]
 pub struct UR {
     ur_type: URType,
     cbor: CBOR,
@@ -12,44 +33,83 @@ pub struct UR {
 
 impl UR {
     /// Creates a new UR from the provided type and CBOR.
+    ///
+    /// A `CBOR` value is always serialized deterministically, so any UR created
+    /// this way has a conforming dCBOR payload.
     pub fn new(
         ur_type: impl TryInto<URType, Error = Error>,
         cbor: impl Into<CBOR>,
     ) -> Result<UR> {
         let ur_type = ur_type.try_into()?;
-        let cbor = cbor.into();
-        Ok(UR { ur_type, cbor })
+        Ok(Self::from_parts(ur_type, cbor.into()))
+    }
+
+    pub(crate) fn from_parts(ur_type: URType, cbor: CBOR) -> UR {
+        UR { ur_type, cbor }
     }
 
     /// Creates a new UR from the provided UR string.
+    ///
+    /// Malformed strings are reported as [`Error::Parse`], which carries the
+    /// byte offset and segment of the problem. The underlying error, such as
+    /// [`Error::InvalidType`], is available from
+    /// [`ParseError::reason`](crate::ParseError::reason).
+    /// Payloads that are not valid dCBOR are rejected with
+    /// [`Error::NonConformant`].
     pub fn from_ur_string(ur_string: impl Into<String>) -> Result<UR> {
-        let ur_string = ur_string.into().to_lowercase();
-        let strip_scheme =
-            ur_string.strip_prefix("ur:").ok_or(Error::InvalidScheme)?;
-        let (ur_type, _) =
-            strip_scheme.split_once('/').ok_or(Error::TypeUnspecified)?;
-        let ur_type = URType::new(ur_type)?;
-        let (kind, data) = decode(&ur_string)?;
-        if kind != ur::ur::Kind::SinglePart {
-            return Err(Error::NotSinglePart);
-        }
-        let cbor = CBOR::try_from_data(data)?;
-        Ok(UR { ur_type, cbor })
+        Ok(Self::from_ur_string_with_mode(ur_string, DecodeMode::Strict)?.0)
     }
 
-    /// Returns the String representation of the UR.
-    pub fn string(&self) -> String {
-        let data = self.cbor.to_cbor_data();
-        ur::encode(&data, &ur::Type::Custom(self.ur_type.string()))
+    /// Creates a new UR from the provided UR string, checking the payload for
+    /// dCBOR conformance according to `mode`.
+    ///
+    /// In [`DecodeMode::Permissive`], a well-formed but non-deterministic
+    /// payload is converted to canonical form and the problems found are
+    /// returned alongside the UR.
+    pub fn from_ur_string_with_mode(
+        ur_string: impl Into<String>,
+        mode: DecodeMode,
+    ) -> Result<(UR, Vec<Nonconformance>)> {
+        let (ur_type, data) = RawUR::from_ur_string(ur_string)?.into_parts();
+        let data = Wiped(data);
+        let (cbor, nonconformances) = decode_cbor(&data, mode)?;
+        Ok((Self::from_parts(ur_type, cbor), nonconformances))
     }
 
+    /// Returns the String representation of the UR.
+    pub fn string(&self) -> String { self.string_in_case(Case::Lower) }
+
     /// Returns the String representation of the UR in uppercase,
     /// most-efficient for QR codes.
-    pub fn qr_string(&self) -> String { self.string().to_uppercase() }
+    pub fn qr_string(&self) -> String { self.string_in_case(Case::Upper) }
 
     /// Returns the data representation of the UR in uppercase,
     /// most-efficient for QR codes.
-    pub fn qr_data(&self) -> Vec<u8> { self.qr_string().as_bytes().to_vec() }
+    pub fn qr_data(&self) -> Vec<u8> { self.qr_string().into_bytes() }
+
+    fn string_in_case(&self, case: Case) -> String {
+        let data = Wiped(self.cbor.to_cbor_data());
+        let mut s = String::with_capacity(ur_string_len(&self.ur_type, &data));
+        write_ur(&mut s, &self.ur_type, &data, case).unwrap();
+        s
+    }
+
+    /// Writes the UR string to `w` in the given case, streaming the scheme,
+    /// type, and bytewords without building an intermediate `String`.
+    pub fn write_to(&self, w: &mut impl fmt::Write, case: Case) -> fmt::Result {
+        write_ur(w, &self.ur_type, &Wiped(self.cbor.to_cbor_data()), case)
+    }
+
+    /// Writes the UR string to `w` in the given case. For many small writes,
+    /// wrap `w` in a `BufWriter`.
+    #[cfg(feature = "std")]
+    pub fn write_to_io(
+        &self,
+        w: &mut impl std::io::Write,
+        case: Case,
+    ) -> std::io::Result<()> {
+        write_ur_io(w, &self.ur_type, &Wiped(self.cbor.to_cbor_data()), case)
+    }
 
     /// Checks the UR type against the provided type.
     pub fn check_type(
@@ -71,7 +131,101 @@ impl UR {
     /// Returns the UR type.
     pub fn ur_type_str(&self) -> &str { self.ur_type.string() }
 
+    /// Returns the CBOR payload.
+    ///
+    /// `CBOR` is reference counted, so this does not copy the payload, but the
+    /// returned value shares it with the UR. Decoders that need to take
+    /// ownership of large byte strings should use [`into_parts`] instead.
+    ///
+    /// [`into_parts`]: Self::into_parts
     pub fn cbor(&self) -> CBOR { self.cbor.clone() }
+
+    /// Returns a reference to the CBOR payload.
+    pub fn cbor_ref(&self) -> &CBOR { &self.cbor }
+
+    /// Consumes the UR and returns its type and CBOR payload.
+    pub fn into_parts(self) -> (URType, CBOR) { (self.ur_type, self.cbor) }
+
+    /// Returns `true` if the UR's type [is sensitive](URType::is_sensitive).
+    pub fn is_sensitive(&self) -> bool { self.ur_type.is_sensitive() }
+
+    /// Returns a value whose `Display` output is the UR string or, if the
+    /// UR's type is sensitive, only its type, as `ur:seed/<redacted>`.
+    pub fn redacted(&self) -> impl fmt::Display + '_ {
+        Redacted { ur_type: &self.ur_type, value: self }
+    }
+
+    /// Returns the payload in CBOR diagnostic notation, annotated with the
+    /// names of tags, e.g. `1(1675854714)   / date /`.
+    ///
+    /// A tag is named as in the global tags store or, failing that, by the UR
+    /// type registered for it in the global UR type registry, e.g.
+    /// `40300(0)   / seed /`. Called from within
+    /// [`with_ur_types_mut`](crate::with_ur_types_mut), only the tags store
+    /// is consulted.
+    pub fn diagnostic(&self) -> String {
+        crate::ur_type_registry::try_with_ur_types(|registry| {
+            let tags = RegistryTags(registry);
+            self.cbor.diagnostic_opt(
+                &DiagFormatOpts::default()
+                    .annotate(true)
+                    .tags(TagsStoreOpt::Custom(&tags)),
+            )
+        })
+        .unwrap_or_else(|| self.cbor.diagnostic_annotated())
+    }
+
+    /// Returns the encoded payload as hex, one item per line, with each item's
+    /// meaning alongside it.
+    pub fn annotated_hex(&self) -> String { self.cbor.hex_annotated() }
+
+    /// Returns a short code for comparing URs, such as one shown on two
+    /// devices, which can be rendered with [`bytewords::identifier`] or
+    /// [`bytewords::bytemoji_identifier`].
+    ///
+    /// The fingerprint is the first 4 bytes of the SHA-256 digest of the type,
+    /// a `/`, and the canonical dCBOR encoding of the payload. For example, the
+    /// digest of `test/` followed by `83010203` begins `805fbafe`. Two URs
+    /// differing only in letter case have the same fingerprint.
+    pub fn fingerprint(&self) -> [u8; 4] {
+        let digest = self.fingerprint_digest();
+        [digest[0], digest[1], digest[2], digest[3]]
+    }
+
+    /// Returns the first 16 bytes of the digest from which the
+    /// [`fingerprint`](Self::fingerprint) is taken, for comparisons where 32
+    /// bits is too few. Render it with [`bytewords::encode`].
+    pub fn long_fingerprint(&self) -> [u8; 16] {
+        let digest = self.fingerprint_digest();
+        let mut fingerprint = [0; 16];
+        fingerprint.copy_from_slice(&digest[..16]);
+        fingerprint
+    }
+
+    fn fingerprint_digest(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.ur_type.string());
+        hasher.update(b"/");
+        hasher.update(&*Wiped(self.cbor.to_cbor_data()));
+        hasher.finalize().into()
+    }
+
+    /// Returns a one-line summary of the UR: its type, the length of its
+    /// encoded payload, and the payload's CRC32 checksum as hex and as a
+    /// bytewords identifier, e.g.
+    /// `ur:test, 4 bytes, crc32 74559fc6, id jury gyro note skew`. The
+    /// identifier's minimal form ends the UR string.
+    pub fn summary(&self) -> String {
+        let data = Wiped(self.cbor.to_cbor_data());
+        let crc = bytewords::crc32(&data);
+        format!(
+            "ur:{}, {} bytes, crc32 {:08x}, id {}",
+            self.ur_type.string(),
+            data.len(),
+            crc,
+            bytewords::identifier(&crc.to_be_bytes())
+        )
+    }
 }
 
 impl From<UR> for CBOR {
@@ -88,12 +242,97 @@ impl TryFrom<String> for UR {
     fn try_from(value: String) -> Result<Self> { UR::from_ur_string(value) }
 }
 
-impl std::fmt::Display for UR {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.string())
+impl fmt::Debug for UR {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("UR");
+        s.field("ur_type", &self.ur_type);
+        if self.ur_type.should_redact() {
+            s.field("cbor", &format_args!("<redacted>"));
+        } else {
+            s.field("cbor", &self.cbor);
+        }
+        s.finish()
+    }
+}
+
+impl fmt::Display for UR {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_to(f, Case::Lower)
     }
 }
 
+/// Displays `value`, or for a sensitive type only the type, as
+/// `ur:seed/<redacted>`.
+pub(crate) struct Redacted<'a, T> {
+    pub(crate) ur_type: &'a URType,
+    pub(crate) value: &'a T,
+}
+
+impl<T: fmt::Display> fmt::Display for Redacted<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ur_type.should_redact() {
+            return write!(f, "ur:{}/<redacted>", self.ur_type.string());
+        }
+        self.value.fmt(f)
+    }
+}
+
+/// Returns the length of the UR string for the given type and payload.
+pub(crate) fn ur_string_len(ur_type: &URType, data: &[u8]) -> usize {
+    "ur:".len() + ur_type.string().len() + "/".len() + (data.len() + 4) * 2
+}
+
+/// Writes a single-part UR string for the given type and encoded payload.
+pub(crate) fn write_ur(
+    w: &mut impl fmt::Write,
+    ur_type: &URType,
+    data: &[u8],
+    case: Case,
+) -> fmt::Result {
+    match case {
+        Case::Lower => {
+            w.write_str("ur:")?;
+            w.write_str(ur_type.string())?;
+        }
+        Case::Upper => {
+            w.write_str("UR:")?;
+            for c in ur_type.string().chars() {
+                w.write_char(c.to_ascii_uppercase())?;
+            }
+        }
+    }
+    w.write_char('/')?;
+    bytewords::write_minimal(w, data, case)
+}
+
+/// Writes a single-part UR string to an `io::Write`.
+#[cfg(feature = "std")]
+pub(crate) fn write_ur_io(
+    w: &mut impl std::io::Write,
+    ur_type: &URType,
+    data: &[u8],
+    case: Case,
+) -> std::io::Result<()> {
+    // Adapts `io::Write` to `fmt::Write`, keeping the underlying I/O error.
+    struct Adapter<'a, W> {
+        inner: &'a mut W,
+        error: Option<std::io::Error>,
+    }
+
+    impl<W: std::io::Write> fmt::Write for Adapter<'_, W> {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.inner.write_all(s.as_bytes()).map_err(|err| {
+                self.error = Some(err);
+                fmt::Error
+            })
+        }
+    }
+
+    let mut adapter = Adapter { inner: w, error: None };
+    write_ur(&mut adapter, ur_type, data, case)
+        .map_err(|_| adapter.error.take().unwrap())
+}
+
 impl AsRef<UR> for UR {
     fn as_ref(&self) -> &UR { self }
 }
@@ -117,4 +356,133 @@ mod tests {
         assert_eq!(ur.ur_type_str(), "test");
         assert_eq!(&ur.cbor, &cbor);
     }
+
+    #[test]
+    fn test_decode_mode() {
+        // [1, 2, 3] with the 3 encoded in two bytes.
+        let data = [0x83, 0x01, 0x02, 0x18, 0x03];
+        let ur_string = ur::encode(&data, &ur::Type::Custom("test"));
+
+        assert!(matches!(
+            UR::from_ur_string(ur_string.as_str()),
+            Err(Error::NonConformant(_))
+        ));
+
+        let (ur, nonconformances) =
+            UR::from_ur_string_with_mode(ur_string, DecodeMode::Permissive)
+                .unwrap();
+        assert_eq!(ur.cbor(), CBOR::from(vec![1, 2, 3]));
+        assert_eq!(nonconformances.len(), 1);
+        assert_eq!(nonconformances[0].offset(), 3);
+    }
+
+    #[test]
+    fn test_inspection() {
+        let ur = UR::new("test", vec![1, 2, 3]).unwrap();
+        assert_eq!(ur.diagnostic(), "[1, 2, 3]");
+        assert_eq!(
+            ur.annotated_hex(),
+            [
+                "83      # array(3)",
+                "    01  # unsigned(1)",
+                "    02  # unsigned(2)",
+                "    03  # unsigned(3)",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            ur.summary(),
+            "ur:test, 4 bytes, crc32 74559fc6, id jury gyro note skew"
+        );
+
+        dcbor::register_tags();
+        let date =
+            UR::new("test", CBOR::to_tagged_value(1, 1675854714)).unwrap();
+        assert_eq!(date.diagnostic(), "1(1675854714)   / date /");
+
+        // Tags that the tags store does not name are named by their UR type,
+        // unless the registry is being written.
+        let seed = UR::new("test", CBOR::to_tagged_value(40300, 0)).unwrap();
+        assert_eq!(seed.diagnostic(), "40300(0)   / seed /");
+        assert_eq!(crate::with_ur_types_mut(|_| seed.diagnostic()), "40300(0)");
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let ur = UR::new("test", vec![1, 2, 3]).unwrap();
+        assert_eq!(ur.fingerprint(), [0x80, 0x5f, 0xba, 0xfe]);
+        assert_eq!(
+            hex::encode(ur.long_fingerprint()),
+            "805fbafe99e438f8a06fb833fa01e86d"
+        );
+        assert_eq!(
+            bytewords::identifier(&ur.fingerprint()),
+            "lava hope road zone"
+        );
+        let upper = UR::from_ur_string(ur.qr_string()).unwrap();
+        assert_eq!(upper.fingerprint(), ur.fingerprint());
+
+        // The type is part of the fingerprint.
+        let other = UR::new("other", vec![1, 2, 3]).unwrap();
+        assert_ne!(other.fingerprint(), ur.fingerprint());
+    }
+
+    #[test]
+    fn test_redaction() {
+        let seed = UR::new("seed", CBOR::to_byte_string([0xaa; 16])).unwrap();
+        assert!(seed.is_sensitive());
+        assert_eq!(seed.to_string(), seed.string());
+        assert_eq!(UR::from_ur_string(seed.to_string()).unwrap(), seed);
+        assert_eq!(
+            format!("{seed:?}"),
+            r#"UR { ur_type: URType("seed"), cbor: <redacted> }"#
+        );
+        assert_eq!(seed.redacted().to_string(), "ur:seed/<redacted>");
+        assert!(seed.string().starts_with("ur:seed/gd"));
+
+        let ur = UR::new("test", vec![1, 2, 3]).unwrap();
+        assert!(!ur.is_sensitive());
+        assert_eq!(ur.to_string(), ur.string());
+        assert_eq!(ur.redacted().to_string(), ur.string());
+        assert_eq!(
+            format!("{ur:?}"),
+            r#"UR { ur_type: URType("test"), cbor: array([unsigned(1), unsigned(2), unsigned(3)]) }"#
+        );
+
+        // Formatting while the registry is written redacts rather than
+        // waiting for it.
+        let redacted = crate::with_ur_types_mut(|_| {
+            (format!("{ur:?}"), ur.redacted().to_string(), ur.to_string())
+        });
+        assert_eq!(
+            redacted,
+            (
+                r#"UR { ur_type: URType("test"), cbor: <redacted> }"#.into(),
+                "ur:test/<redacted>".into(),
+                ur.string()
+            )
+        );
+
+        // A type marked sensitive is redacted in URs created before.
+        let secret = UR::new("test-secret", vec![1, 2, 3]).unwrap();
+        crate::mark_ur_type_sensitive("test-secret").unwrap();
+        assert!(secret.is_sensitive());
+        assert_eq!(secret.redacted().to_string(), "ur:test-secret/<redacted>");
+    }
+
+    #[test]
+    fn test_write_to() {
+        let ur = UR::new("test", vec![1, 2, 3]).unwrap();
+
+        let mut s = String::new();
+        ur.write_to(&mut s, Case::Upper).unwrap();
+        assert_eq!(s, "UR:TEST/LSADAOAXJYGONESW");
+        assert_eq!(ur.qr_string(), s);
+        assert_eq!(ur.to_string(), "ur:test/lsadaoaxjygonesw");
+
+        let mut bytes = Vec::new();
+        ur.write_to_io(&mut bytes, Case::Lower).unwrap();
+        assert_eq!(bytes, b"ur:test/lsadaoaxjygonesw");
+        assert_eq!(ur.qr_data(), b"UR:TEST/LSADAOAXJYGONESW");
+    }
 }
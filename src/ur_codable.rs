@@ -1,7 +1,9 @@
+import_stdlib!();
+
 use crate::{URDecodable, UREncodable};
 
 /// A type that can be encoded to and decoded from a UR.
-pub trait URCodable {}
+pub trait URCodable: UREncodable + URDecodable {}
 
 impl<T> URCodable for T where T: UREncodable + URDecodable {}
 
@@ -10,6 +12,7 @@ mod tests {
     use dcbor::prelude::*;
 
     use super::*;
+    use crate::test_fixtures::TaggedNumber;
 
     #[derive(Debug, PartialEq)]
     struct Test {
@@ -64,4 +67,12 @@ mod tests {
         let test2 = Test::from_ur_string(ur_string).unwrap();
         assert_eq!(test.s, test2.s);
     }
+
+    #[test]
+    fn test_ur_codable_registered_type() {
+        let value = TaggedNumber(42);
+        let ur_string = value.ur_string();
+        assert!(ur_string.starts_with("ur:known-value/"));
+        assert_eq!(TaggedNumber::from_ur_string(ur_string).unwrap(), value);
+    }
 }
@@ -0,0 +1,388 @@
+import_stdlib!();
+
+use bytes::{Buf, BufMut, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{Error, MultipartDecoder, Result, UR};
+
+/// A [`tokio_util::codec`] codec that frames URs as newline-delimited UR
+/// strings, for exchanging them over byte streams such as TCP or serial
+/// links.
+///
+/// Decoding yields a [`UR`] for each single-part UR string, and for each
+/// message completed by a run of multipart UR strings. Lines may be in either
+/// case and may end in `\r\n`, and blank lines are skipped. A multipart part
+/// of a different message than the one in progress abandons it and starts
+/// the new one. Once a message is yielded, any further parts of it start the
+/// message again.
+///
+/// A line that cannot be decoded, such as one garbled in transit, is skipped
+/// rather than returned as an error, which would end a `FramedRead`. This
+/// covers malformed single-part URs and multipart parts, lines that are too
+/// long, and the final part of a message that does not match its checksum.
+/// Skipped lines are counted in the
+/// [`invalid_frames`](crate::DecoderStats::invalid_frames) of the
+/// [`multipart_decoder`](Self::multipart_decoder), and reported to its
+/// observer as [`DecoderEvent::Rejected`](crate::DecoderEvent::Rejected).
+/// Only I/O errors end the stream.
+///
+/// Encoding writes a [`UR`], or a part from a
+/// [`MultipartEncoder`](crate::MultipartEncoder), followed by a newline.
+pub struct URCodec {
+    max_line_len: usize,
+    /// The offset in the buffer from which to resume searching for a newline.
+    next_index: usize,
+    /// Whether the rest of a line that was too long is being skipped.
+    discarding: bool,
+    decoder: MultipartDecoder,
+}
+
+impl URCodec {
+    /// The longest line accepted by default, in bytes.
+    pub const DEFAULT_MAX_LINE_LEN: usize = 1 << 20;
+
+    pub fn new() -> Self {
+        Self {
+            max_line_len: Self::DEFAULT_MAX_LINE_LEN,
+            next_index: 0,
+            discarding: false,
+            decoder: MultipartDecoder::new().with_auto_restart(true),
+        }
+    }
+
+    /// Skips lines longer than `max_line_len` bytes, not counting the line
+    /// ending, reporting each as [`Error::LineTooLong`].
+    pub fn with_max_line_len(mut self, max_line_len: usize) -> Self {
+        self.max_line_len = max_line_len;
+        self
+    }
+
+    /// Returns the longest line accepted, in bytes.
+    pub fn max_line_len(&self) -> usize { self.max_line_len }
+
+    /// Returns the decoder for the multipart message in progress.
+    pub fn multipart_decoder(&self) -> &MultipartDecoder { &self.decoder }
+
+    fn decode_line(&mut self, line: &[u8]) -> Option<UR> {
+        let line = String::from_utf8_lossy(line);
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+        if line.matches('/').count() < 2 {
+            return UR::from_ur_string(line)
+                .inspect_err(|err| self.decoder.reject_frame(err))
+                .ok();
+        }
+
+        // A rejected part is counted in the decoder's statistics. A failed
+        // checksum rejects the final part but leaves the decoder complete.
+        _ = self.decoder.receive(line);
+        if !self.decoder.is_complete() {
+            return None;
+        }
+        let message = match self.decoder.message() {
+            Ok(message) => message,
+            Err(Error::ChecksumMismatch) => None,
+            Err(err) => {
+                self.decoder.reject(&err);
+                None
+            }
+        };
+        self.decoder.reset();
+        message
+    }
+
+    fn reject_long_line(&mut self) {
+        self.decoder
+            .reject_frame(&Error::LineTooLong(self.max_line_len));
+    }
+}
+
+impl Default for URCodec {
+    fn default() -> Self { Self::new() }
+}
+
+impl Decoder for URCodec {
+    type Item = UR;
+    type Error = Error;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<UR>> {
+        loop {
+            // Look no further than the longest line and a `\r\n`, which is
+            // enough to tell that a line is too long.
+            let read_to = buf.len().min(self.max_line_len.saturating_add(2));
+            let newline = buf[self.next_index..read_to]
+                .iter()
+                .position(|&b| b == b'\n')
+                .map(|offset| offset + self.next_index);
+            match (self.discarding, newline) {
+                (true, Some(index)) => {
+                    buf.advance(index + 1);
+                    self.discarding = false;
+                    self.next_index = 0;
+                }
+                (true, None) => {
+                    buf.advance(read_to);
+                    self.next_index = 0;
+                    if buf.is_empty() {
+                        return Ok(None);
+                    }
+                }
+                (false, Some(index)) => {
+                    self.next_index = 0;
+                    let line = buf.split_to(index + 1);
+                    let line = &line[..index];
+                    let line = line.strip_suffix(b"\r").unwrap_or(line);
+                    if line.len() > self.max_line_len {
+                        self.reject_long_line();
+                    } else if let Some(ur) = self.decode_line(line) {
+                        return Ok(Some(ur));
+                    }
+                }
+                (false, None)
+                    if buf.len() > self.max_line_len.saturating_add(1) =>
+                {
+                    self.discarding = true;
+                    self.reject_long_line();
+                }
+                (false, None) => {
+                    self.next_index = read_to;
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<UR>> {
+        if let Some(ur) = self.decode(buf)? {
+            return Ok(Some(ur));
+        }
+        self.next_index = 0;
+        let line = buf.split();
+        if self.discarding {
+            self.discarding = false;
+            return Ok(None);
+        }
+        // The final line need not end in a newline.
+        Ok(self.decode_line(&line))
+    }
+}
+
+impl Encoder<&UR> for URCodec {
+    type Error = Error;
+
+    fn encode(&mut self, ur: &UR, dst: &mut BytesMut) -> Result<()> {
+        self.encode(ur.string(), dst)
+    }
+}
+
+impl Encoder<UR> for URCodec {
+    type Error = Error;
+
+    fn encode(&mut self, ur: UR, dst: &mut BytesMut) -> Result<()> {
+        self.encode(&ur, dst)
+    }
+}
+
+/// Writes a UR string, such as a multipart part, as a line.
+impl Encoder<String> for URCodec {
+    type Error = Error;
+
+    fn encode(&mut self, ur_string: String, dst: &mut BytesMut) -> Result<()> {
+        dst.reserve(ur_string.len() + 1);
+        dst.put_slice(ur_string.as_bytes());
+        dst.put_u8(b'\n');
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dcbor::prelude::*;
+
+    use super::*;
+    use crate::{
+        DecoderEvent, FountainPart, MultipartEncoder, bytewords::Style,
+    };
+
+    fn ur(message: &str) -> UR {
+        UR::new("bytes", CBOR::to_byte_string(message.as_bytes())).unwrap()
+    }
+
+    fn decode_all(codec: &mut URCodec, input: &str) -> Vec<Result<UR>> {
+        let mut buf = BytesMut::from(input);
+        let mut results = Vec::new();
+        loop {
+            match codec.decode_eof(&mut buf) {
+                Ok(Some(ur)) => results.push(Ok(ur)),
+                Ok(None) => break,
+                Err(err) => results.push(Err(err)),
+            }
+        }
+        results
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<URCodec>();
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let single = ur("hello");
+        let multi = ur("The only thing we have to fear is fear itself.");
+        let parts = MultipartEncoder::new(&multi, 10).unwrap().cycles(1);
+
+        let mut codec = URCodec::new();
+        let mut buf = BytesMut::new();
+        codec.encode(&single, &mut buf).unwrap();
+        for part in parts {
+            codec.encode(part, &mut buf).unwrap();
+        }
+        codec.encode(single.clone(), &mut buf).unwrap();
+
+        let mut decoded = Vec::new();
+        // Feed the stream a few bytes at a time.
+        let mut input = BytesMut::new();
+        for chunk in buf.chunks(7) {
+            input.extend_from_slice(chunk);
+            while let Some(ur) = codec.decode(&mut input).unwrap() {
+                decoded.push(ur);
+            }
+        }
+        assert!(input.is_empty());
+        assert_eq!(decoded, [single.clone(), multi, single]);
+    }
+
+    #[test]
+    fn test_line_handling() {
+        let single = ur("hello");
+        let input = format!(
+            "\r\n{}\r\n\n  {}  \n{}",
+            single.string(),
+            single.qr_string(),
+            single.string()
+        );
+        let results = decode_all(&mut URCodec::new(), &input);
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|r| r.as_ref().unwrap() == &single));
+    }
+
+    #[test]
+    fn test_max_line_len() {
+        let single = ur("hello");
+        let len = single.string().len();
+        let input = format!("{}\r\n{}x\n{}", single, single, single);
+
+        let mut codec = URCodec::new().with_max_line_len(len);
+        let results = decode_all(&mut codec, &input);
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.as_ref().unwrap() == &single));
+        assert_eq!(codec.multipart_decoder().stats().invalid_frames(), 1);
+
+        // A long line is rejected before its end has arrived, and the rest of
+        // it is skipped.
+        let mut codec = URCodec::new().with_max_line_len(4);
+        let mut buf = BytesMut::from("ur:bytes");
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert_eq!(codec.multipart_decoder().stats().invalid_frames(), 1);
+        buf.extend_from_slice(b"/more\nur:");
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert_eq!(&buf[..], b"ur:");
+        assert_eq!(codec.multipart_decoder().stats().invalid_frames(), 1);
+
+        let mut codec = URCodec::new().with_max_line_len(usize::MAX);
+        assert_eq!(
+            codec.decode(&mut BytesMut::from("ur:bytes")).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_new_message_restarts() {
+        let first = ur("The only thing we have to fear is fear itself.");
+        let second = ur("Ask not what your country can do for you.");
+        let mut first_parts = MultipartEncoder::new(&first, 10).unwrap();
+        let second_parts = MultipartEncoder::new(&second, 10).unwrap();
+
+        let mut input = String::new();
+        for _ in 0..2 {
+            input.push_str(&first_parts.next_part().unwrap());
+            input.push('\n');
+        }
+        for part in second_parts.cycles(1) {
+            input.push_str(&part);
+            input.push('\n');
+        }
+        let results = decode_all(&mut URCodec::new(), &input);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].as_ref().unwrap(), &second);
+    }
+
+    #[test]
+    fn test_invalid_part_skipped() {
+        let multi = ur("The only thing we have to fear is fear itself.");
+        let mut input = String::new();
+        for (i, part) in MultipartEncoder::new(&multi, 10)
+            .unwrap()
+            .cycles(1)
+            .enumerate()
+        {
+            if i == 2 {
+                input.push_str("ur:bytes/3-5/nonsense\n");
+            }
+            input.push_str(&part);
+            input.push('\n');
+        }
+        let mut codec = URCodec::new();
+        let results = decode_all(&mut codec, &input);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].as_ref().unwrap(), &multi);
+        assert_eq!(codec.multipart_decoder().stats().invalid_frames(), 1);
+    }
+
+    #[test]
+    fn test_invalid_line_skipped() {
+        use std::sync::{Arc, Mutex};
+
+        let single = ur("hello");
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let observed = Arc::clone(&events);
+        let decoder = MultipartDecoder::new()
+            .with_auto_restart(true)
+            .with_observer(move |event| {
+                observed.lock().unwrap().push(event.clone());
+            });
+        let mut codec = URCodec { decoder, ..URCodec::new() };
+        let input = format!("ur:bytes/nonsense\n{}\n", single);
+        let results = decode_all(&mut codec, &input);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].as_ref().unwrap(), &single);
+        let stats = codec.multipart_decoder().stats();
+        assert_eq!(stats.frames_received(), 1);
+        assert_eq!(stats.invalid_frames(), 1);
+        assert!(matches!(
+            events.lock().unwrap()[..],
+            [DecoderEvent::Rejected(_)]
+        ));
+    }
+
+    #[test]
+    fn test_checksum_mismatch_skipped() {
+        let single = ur("hello");
+        let part = FountainPart::new(1, 1, 4, 0, vec![1, 2, 3, 4]);
+        let input = format!(
+            "ur:bytes/1-1/{}\n{}\n",
+            crate::bytewords::encode(part.cbor_data(), Style::Minimal),
+            single
+        );
+        let mut codec = URCodec::new();
+        let results = decode_all(&mut codec, &input);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].as_ref().unwrap(), &single);
+        assert_eq!(codec.multipart_decoder().stats().invalid_frames(), 1);
+        assert!(!codec.multipart_decoder().is_complete());
+    }
+}
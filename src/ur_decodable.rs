@@ -1,6 +1,8 @@
+import_stdlib!();
+
 use dcbor::prelude::*;
 
-use crate::UR;
+use crate::{UR, ur_type_registry::ur_type_for};
 
 /// A type that can be decoded from a UR.
 pub trait URDecodable: CBORTaggedDecodable {
@@ -8,17 +10,27 @@ pub trait URDecodable: CBORTaggedDecodable {
     where
         Self: Sized,
     {
-        ur.as_ref().check_type(
-            Self::cbor_tags()[0].name().as_ref().unwrap().clone(),
-        )?;
-        Self::from_untagged_cbor(ur.as_ref().clone().into())
+        let ur = ur.as_ref();
+        ur.check_type(ur_type_for::<Self>()?)?;
+        Self::from_untagged_cbor(ur.cbor())
+    }
+
+    /// Decodes the UR, consuming it so that a payload not shared with any
+    /// other value can be moved into `Self` rather than copied.
+    fn from_ur_owned(ur: UR) -> dcbor::Result<Self>
+    where
+        Self: Sized,
+    {
+        ur.check_type(ur_type_for::<Self>()?)?;
+        let (_, cbor) = ur.into_parts();
+        Self::from_untagged_cbor(cbor)
     }
 
     fn from_ur_string(ur_string: impl Into<String>) -> dcbor::Result<Self>
     where
         Self: Sized,
     {
-        Self::from_ur(UR::from_ur_string(ur_string)?)
+        Self::from_ur_owned(UR::from_ur_string(ur_string)?)
     }
 }
 
@@ -0,0 +1,166 @@
+import_stdlib!();
+
+use core::any::Any;
+
+use crate::{
+    Error, Result, UR, URDecodable, URType, ur_type_registry::ur_type_for,
+};
+
+type DecoderFn<T> = Box<dyn Fn(&UR) -> Result<T> + Send + Sync>;
+
+/// Decodes URs of many types by dispatching on the UR type to a registered
+/// decoder.
+///
+/// The output type `T` is typically an application enum with one variant per
+/// supported type, or `Box<dyn Any>` when the set of types is open-ended.
+///
+/// ```
+/// use bc_ur::prelude::*;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Scanned {
+///     Test(CBOR),
+/// }
+///
+/// let mut dispatcher = URDispatcher::new();
+/// dispatcher
+///     .register("test", |ur| Ok(Scanned::Test(ur.cbor())))
+///     .unwrap();
+///
+/// let ur = UR::from_ur_string("ur:test/lsadaoaxjygonesw").unwrap();
+/// let scanned = dispatcher.decode(&ur).unwrap();
+/// assert_eq!(scanned, Scanned::Test(vec![1, 2, 3].into()));
+/// ```
+pub struct URDispatcher<T = Box<dyn Any>> {
+    decoders: HashMap<String, DecoderFn<T>>,
+}
+
+impl<T> URDispatcher<T> {
+    pub fn new() -> Self { Self { decoders: HashMap::new() } }
+
+    /// Registers a decoder for the given UR type, replacing any previous
+    /// decoder for that type.
+    pub fn register(
+        &mut self,
+        ur_type: impl TryInto<URType, Error = Error>,
+        decoder: impl Fn(&UR) -> Result<T> + Send + Sync + 'static,
+    ) -> Result<()> {
+        let ur_type = ur_type.try_into()?;
+        self.decoders
+            .insert(ur_type.string().to_string(), Box::new(decoder));
+        Ok(())
+    }
+
+    /// Registers a decoder for the UR type of `D`, which is decoded with
+    /// [`URDecodable::from_ur`] and then converted by `f`.
+    ///
+    /// The UR type is the name of `D`'s first CBOR tag, or the type
+    /// registered for it in the global
+    /// [`URTypeRegistry`](crate::URTypeRegistry).
+    pub fn register_type<D>(
+        &mut self,
+        f: impl Fn(D) -> T + Send + Sync + 'static,
+    ) -> Result<()>
+    where
+        D: URDecodable + 'static,
+    {
+        self.register(ur_type_for::<D>()?, move |ur| Ok(f(D::from_ur(ur)?)))
+    }
+
+    /// Returns `true` if a decoder is registered for the given UR type.
+    pub fn supports(&self, ur_type: &str) -> bool {
+        self.decoders.contains_key(ur_type)
+    }
+
+    /// Returns the UR types that have registered decoders, in no particular
+    /// order.
+    pub fn ur_types(&self) -> impl Iterator<Item = &str> {
+        self.decoders.keys().map(String::as_str)
+    }
+
+    /// Decodes the UR with the decoder registered for its type.
+    pub fn decode(&self, ur: &UR) -> Result<T> {
+        let decoder = self
+            .decoders
+            .get(ur.ur_type_str())
+            .ok_or_else(|| Error::UnsupportedType(ur.ur_type_str().into()))?;
+        decoder(ur)
+    }
+
+    /// Parses the UR string and decodes it with the decoder registered for its
+    /// type.
+    pub fn decode_string(&self, ur_string: impl Into<String>) -> Result<T> {
+        self.decode(&UR::from_ur_string(ur_string)?)
+    }
+}
+
+impl URDispatcher<Box<dyn Any>> {
+    /// Registers `D` so that [`decode`](Self::decode) returns a boxed `D`
+    /// that can be recovered with `downcast`.
+    pub fn register_any<D>(&mut self) -> Result<()>
+    where
+        D: URDecodable + 'static,
+    {
+        self.register_type(|value: D| Box::new(value) as Box<dyn Any>)
+    }
+}
+
+impl<T> Default for URDispatcher<T> {
+    fn default() -> Self { Self::new() }
+}
+
+#[cfg(test)]
+mod tests {
+    use dcbor::prelude::*;
+
+    use super::*;
+    use crate::{UREncodable, test_fixtures::TaggedNumber};
+
+    #[derive(Debug, PartialEq)]
+    enum Scanned {
+        Number(TaggedNumber),
+        Test(CBOR),
+    }
+
+    #[test]
+    fn test_dispatch_enum() {
+        let mut dispatcher = URDispatcher::new();
+        dispatcher.register_type(Scanned::Number).unwrap();
+        dispatcher
+            .register("test", |ur| Ok(Scanned::Test(ur.cbor())))
+            .unwrap();
+        assert!(dispatcher.supports(TaggedNumber::UR_TYPE));
+
+        assert_eq!(
+            dispatcher.decode(&TaggedNumber(7).ur()).unwrap(),
+            Scanned::Number(TaggedNumber(7))
+        );
+        assert_eq!(
+            dispatcher
+                .decode_string("ur:test/lsadaoaxjygonesw")
+                .unwrap(),
+            Scanned::Test(vec![1, 2, 3].into())
+        );
+    }
+
+    #[test]
+    fn test_dispatch_any() {
+        let mut dispatcher = URDispatcher::default();
+        dispatcher.register_any::<TaggedNumber>().unwrap();
+        let decoded = dispatcher.decode(&TaggedNumber(7).ur()).unwrap();
+        assert_eq!(
+            decoded.downcast_ref::<TaggedNumber>(),
+            Some(&TaggedNumber(7))
+        );
+    }
+
+    #[test]
+    fn test_unsupported_type() {
+        let dispatcher: URDispatcher = URDispatcher::new();
+        let ur = UR::from_ur_string("ur:test/lsadaoaxjygonesw").unwrap();
+        assert!(matches!(
+            dispatcher.decode(&ur),
+            Err(Error::UnsupportedType(t)) if t == "test"
+        ));
+    }
+}
@@ -1,17 +1,23 @@
+import_stdlib!();
+
 use dcbor::prelude::*;
 
-use crate::ur::UR;
+use crate::{ur::UR, ur_type_registry::ur_type_for_tag};
 
 /// A type that can be encoded to a UR.
 pub trait UREncodable: CBORTaggedEncodable {
     /// Returns the UR representation of the object.
+    ///
+    /// The UR type is the name of the type's first CBOR tag or, if the tag is
+    /// unnamed, the type registered for it in the global
+    /// [`URTypeRegistry`](crate::URTypeRegistry).
     fn ur(&self) -> UR {
         let tag = &Self::cbor_tags()[0];
-        if let Some(name) = tag.name().as_ref() {
-            UR::new(name.clone(), self.untagged_cbor()).unwrap()
+        if let Some(name) = ur_type_for_tag(tag) {
+            UR::new(name, self.untagged_cbor()).unwrap()
         } else {
             panic!(
-                "CBOR tag {} must have a name. Did you call `register_tags()`?",
+                "CBOR tag {} must have a name. Did you call `register_tags()` or `register_ur_type()`?",
                 tag.value()
             );
         }
@@ -0,0 +1,559 @@
+//! Compile-time validation of UR string literals for the [`ur!`](crate::ur)
+//! macro.
+//!
+//! Everything here is a `const fn` that panics on invalid input, so that a
+//! malformed literal fails the build. The checks mirror the runtime parser
+//! without allocating: bytewords are decoded one byte at a time as needed.
+//!
+//! # Rejected literals
+//!
+//! Each of the following fails to compile.
+//!
+//! A scheme other than `ur:`:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("xr:test/lsadaoaxjygonesw");
+//! ```
+//!
+//! An empty literal:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("");
+//! ```
+//!
+//! No type:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:test");
+//! ```
+//!
+//! An invalid character in the type:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:te_st/lsadaoaxjygonesw");
+//! ```
+//!
+//! A type beginning with a hyphen:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:-test/lsadaoaxjygonesw");
+//! ```
+//!
+//! A type longer than `URType::MAX_LEN`:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa/lsadaoaxjygonesw");
+//! ```
+//!
+//! A multipart UR:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:test/1-2/lsadaoaxjygonesw");
+//! ```
+//!
+//! A bad checksum:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:test/lsadaoaxjygoneso");
+//! ```
+//!
+//! An odd number of letters:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:test/lsadaoaxjygonesww");
+//! ```
+//!
+//! A pair of letters that is not a byteword:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:test/lsadaoaxjygonxsw");
+//! ```
+//!
+//! A payload too short for its checksum:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:test/aeae");
+//! ```
+//!
+//! An empty payload:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:test/aeaeaeae");
+//! ```
+//!
+//! A truncated array, `830102`:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:test/lsadaowmdidwol");
+//! ```
+//!
+//! A truncated argument, `8301`:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:test/lsaddsjowlvy");
+//! ```
+//!
+//! An indefinite map with a key but no value, `bf01ff`:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:test/rsadzmahhtrnwf");
+//! ```
+//!
+//! A truncated map, `a1`:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:test/oyjktekewf");
+//! ```
+//!
+//! A break outside an indefinite item, `ff`:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:test/zmzmaeaeae");
+//! ```
+//!
+//! Reserved additional information, `1c`:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:test/ceswaxqdsa");
+//! ```
+//!
+//! A simple value below 32 in two bytes, `f81f`:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:test/yactbeqzjyrs");
+//! ```
+//!
+//! An indefinite-length integer, `1f`:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:test/cthebkvoks");
+//! ```
+//!
+//! A byte string chunk that is not a byte string, `5f01ff`:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:test/headzmpsrojtgu");
+//! ```
+//!
+//! A text chunk in a byte string, `5f6161ff`:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:test/hehshszmpkuytymw");
+//! ```
+//!
+//! Text that is not UTF-8, `62c328`:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:test/idsrdehsjowddn");
+//! ```
+//!
+//! A lone continuation byte in text, `6180`:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:test/hslatiltsbes");
+//! ```
+//!
+//! A truncated UTF-8 sequence in text, `63e08080`:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:test/iavtlalaktbagsft");
+//! ```
+//!
+//! Data after the CBOR item, `0102`:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:test/adaorpsffwmo");
+//! ```
+//!
+//! CBOR nested more deeply than the decoder allows:
+//!
+//! ```compile_fail
+//! bc_ur::ur!("ur:test/lylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylylyaejeisprlk");
+//! ```
+
+use crate::{bytewords::BYTEWORDS, conformance::MAX_NESTING_DEPTH, ur_type};
+
+/// Creates a [`UR`](crate::UR) from a string literal, which is checked at
+/// compile time.
+///
+/// Only the syntax of the literal, its bytewords checksum, and the
+/// well-formedness of its CBOR payload are checked at compile time: the build
+/// fails unless the literal is a single-part UR string, in either case, with
+/// a valid type, valid minimal bytewords and checksum, and a single
+/// well-formed CBOR item as its payload.
+///
+/// Whether the payload is deterministic dCBOR, with shortest-form integers
+/// and floats, sorted map keys, and so on, is not checked at compile time.
+/// It is checked each time the expression is evaluated, which panics if it
+/// is not.
+///
+/// ```
+/// use bc_ur::ur;
+/// use dcbor::prelude::*;
+///
+/// let ur = ur!("ur:test/lsadaoaxjygonesw");
+/// assert_eq!(ur.ur_type_str(), "test");
+/// assert_eq!(ur.cbor(), CBOR::from(vec![1, 2, 3]));
+/// ```
+///
+/// A typo does not compile:
+///
+/// ```compile_fail
+/// let ur = bc_ur::ur!("ur:test/lsadaoaxjygoneso");
+/// ```
+#[macro_export]
+macro_rules! ur {
+    ($ur_string:literal) => {{
+        const _: () = $crate::check_ur_literal($ur_string);
+        $crate::UR::from_ur_string($ur_string)
+            .expect("ur! literal is not deterministic dCBOR")
+    }};
+}
+
+/// Panics unless `ur_string` is a valid single-part UR string with a
+/// well-formed CBOR payload. Used by [`ur!`](crate::ur).
+#[doc(hidden)]
+pub const fn check_ur_literal(ur_string: &str) {
+    let bytes = ur_string.as_bytes();
+    if bytes.len() < 3
+        || lower(bytes[0]) != b'u'
+        || lower(bytes[1]) != b'r'
+        || bytes[2] != b':'
+    {
+        panic!("UR literal must begin with \"ur:\"");
+    }
+
+    let mut slash = 3;
+    while slash < bytes.len() && bytes[slash] != b'/' {
+        slash += 1;
+    }
+    if slash == bytes.len() {
+        panic!("UR literal has no type");
+    }
+    check_type(bytes, 3, slash);
+
+    let payload = Payload { bytes, start: slash + 1 };
+    let mut i = payload.start;
+    while i < bytes.len() {
+        if bytes[i] == b'/' {
+            panic!("ur! does not accept multipart UR literals");
+        }
+        i += 1;
+    }
+    let len = payload.len();
+    if len < 4 {
+        panic!("UR literal payload is too short for its checksum");
+    }
+    let data_len = len - 4;
+
+    let mut crc = !0u32;
+    let mut i = 0;
+    while i < data_len {
+        crc = crc32_update(crc, payload.byte(i));
+        i += 1;
+    }
+    let checksum = (payload.byte(data_len) as u32) << 24
+        | (payload.byte(data_len + 1) as u32) << 16
+        | (payload.byte(data_len + 2) as u32) << 8
+        | payload.byte(data_len + 3) as u32;
+    if !crc != checksum {
+        panic!("UR literal has an invalid bytewords checksum");
+    }
+
+    check_cbor(&payload, data_len);
+}
+
+const fn lower(b: u8) -> u8 { b.to_ascii_lowercase() }
+
+/// Checks the type between `start` and `end`, which may be in either case.
+const fn check_type(bytes: &[u8], start: usize, end: usize) {
+    if end - start > crate::URType::MAX_LEN {
+        panic!("UR literal type is too long");
+    }
+    let ur_type = bytes.split_at(end).0.split_at(start).1;
+    if ur_type::find_problem_in_case(ur_type, true).is_some() {
+        panic!("UR literal has an invalid type");
+    }
+}
+
+/// Maps the first and last letters of each byteword to its value, or -1.
+const MINIMAL_WORDS: [i16; 26 * 26] = {
+    let mut table = [-1; 26 * 26];
+    let mut value = 0;
+    while value < BYTEWORDS.len() {
+        let word = BYTEWORDS[value].as_bytes();
+        table[minimal_index(word[0], word[3])] = value as i16;
+        value += 1;
+    }
+    table
+};
+
+const fn minimal_index(first: u8, last: u8) -> usize {
+    (first - b'a') as usize * 26 + (last - b'a') as usize
+}
+
+/// A minimal bytewords payload, decoded a byte at a time.
+struct Payload<'a> {
+    bytes: &'a [u8],
+    start: usize,
+}
+
+impl Payload<'_> {
+    /// Returns the number of bytes the payload encodes.
+    const fn len(&self) -> usize {
+        let chars = self.bytes.len() - self.start;
+        if !chars.is_multiple_of(2) {
+            panic!("UR literal payload has an odd number of letters");
+        }
+        chars / 2
+    }
+
+    const fn byte(&self, index: usize) -> u8 {
+        let first = lower(self.bytes[self.start + index * 2]);
+        let last = lower(self.bytes[self.start + index * 2 + 1]);
+        if !first.is_ascii_lowercase() || !last.is_ascii_lowercase() {
+            panic!("UR literal payload is not bytewords");
+        }
+        let value = MINIMAL_WORDS[minimal_index(first, last)];
+        if value < 0 {
+            panic!("UR literal payload is not bytewords");
+        }
+        value as u8
+    }
+}
+
+const fn crc32_update(crc: u32, byte: u8) -> u32 {
+    let mut crc = crc ^ byte as u32;
+    let mut bit = 0;
+    while bit < 8 {
+        crc = if crc & 1 != 0 {
+            (crc >> 1) ^ 0xedb8_8320
+        } else {
+            crc >> 1
+        };
+        bit += 1;
+    }
+    crc
+}
+
+/// Checks that the first `len` bytes of the payload are exactly one
+/// well-formed CBOR item that the runtime decoder accepts.
+const fn check_cbor(payload: &Payload<'_>, len: usize) {
+    // For each open container, its major type if it has indefinite length
+    // or zero if not, and the items remaining in it or, if it has indefinite
+    // length, the items so far.
+    let mut indefinite = [0u8; MAX_NESTING_DEPTH];
+    let mut items = [0u64; MAX_NESTING_DEPTH];
+    let mut depth = 0;
+    let mut pos = 0;
+    loop {
+        if pos >= len {
+            panic!("UR literal CBOR is truncated");
+        }
+        let header = payload.byte(pos);
+        pos += 1;
+        let (major, info) = (header >> 5, header & 0x1f);
+        let mut opened = false;
+
+        if header == 0xff {
+            if depth == 0 || indefinite[depth - 1] == 0 {
+                panic!("UR literal CBOR has an unexpected break");
+            }
+            if indefinite[depth - 1] == 5 && !items[depth - 1].is_multiple_of(2)
+            {
+                panic!("UR literal CBOR map has a key without a value");
+            }
+            depth -= 1;
+        } else {
+            let parent = if depth > 0 { indefinite[depth - 1] } else { 0 };
+            if (parent == 2 || parent == 3) && (major != parent || info == 31) {
+                panic!("UR literal CBOR has an invalid string chunk");
+            }
+            let argument = match info {
+                0..=23 => Some(info as u64),
+                24..=27 => {
+                    let count = 1 << (info - 24);
+                    if pos + count > len {
+                        panic!("UR literal CBOR is truncated");
+                    }
+                    let mut value = 0u64;
+                    let mut i = 0;
+                    while i < count {
+                        value = value << 8 | payload.byte(pos + i) as u64;
+                        i += 1;
+                    }
+                    pos += count;
+                    Some(value)
+                }
+                28..=30 => panic!("UR literal CBOR has a reserved header"),
+                _ => None,
+            };
+            let items_in = match (major, argument) {
+                (2..=5, None) => Some((major, 0)),
+                (0 | 1 | 6 | 7, None) => {
+                    panic!("UR literal CBOR has an invalid header")
+                }
+                (2 | 3, Some(n)) => {
+                    if n > (len - pos) as u64 {
+                        panic!("UR literal CBOR is truncated");
+                    }
+                    let end = pos + n as usize;
+                    if major == 3 {
+                        check_utf8(payload, pos, end);
+                    }
+                    pos = end;
+                    None
+                }
+                // Every item takes at least one byte.
+                (4 | 5, Some(n)) if n > (len - pos) as u64 => {
+                    panic!("UR literal CBOR is truncated")
+                }
+                (4, Some(n)) if n > 0 => Some((0, n)),
+                (5, Some(n)) if n > 0 => Some((0, n * 2)),
+                (6, _) => Some((0, 1)),
+                // A one-byte simple value must be at least 32.
+                (7, Some(n)) if info == 24 && n < 32 => {
+                    panic!("UR literal CBOR has an invalid simple value")
+                }
+                _ => None,
+            };
+            if let Some((kind, count)) = items_in {
+                if depth == MAX_NESTING_DEPTH {
+                    panic!("UR literal CBOR nests too deeply");
+                }
+                indefinite[depth] = kind;
+                items[depth] = count;
+                depth += 1;
+                opened = true;
+            }
+        }
+
+        // Unless it opened a container, an item just finished, and finishing
+        // the last item of a definite container finishes the container.
+        if opened {
+            continue;
+        }
+        while depth > 0 {
+            if indefinite[depth - 1] != 0 {
+                items[depth - 1] += 1;
+                break;
+            }
+            items[depth - 1] -= 1;
+            if items[depth - 1] != 0 {
+                break;
+            }
+            depth -= 1;
+        }
+        if depth == 0 {
+            break;
+        }
+    }
+    if pos != len {
+        panic!("UR literal CBOR is followed by unused data");
+    }
+}
+
+/// Panics unless the payload bytes from `start` to `end` are UTF-8.
+const fn check_utf8(payload: &Payload<'_>, start: usize, end: usize) {
+    let mut i = start;
+    while i < end {
+        let b = payload.byte(i);
+        let (count, min, max) = match b {
+            0x00..=0x7f => (0, 0x80, 0xbf),
+            0xc2..=0xdf => (1, 0x80, 0xbf),
+            0xe0 => (2, 0xa0, 0xbf),
+            0xed => (2, 0x80, 0x9f),
+            0xe1..=0xef => (2, 0x80, 0xbf),
+            0xf0 => (3, 0x90, 0xbf),
+            0xf4 => (3, 0x80, 0x8f),
+            0xf1..=0xf3 => (3, 0x80, 0xbf),
+            _ => panic!("UR literal CBOR text is not UTF-8"),
+        };
+        if i + count >= end && count > 0 {
+            panic!("UR literal CBOR text is not UTF-8");
+        }
+        let mut j = 1;
+        while j <= count {
+            let c = payload.byte(i + j);
+            let (low, high) = if j == 1 { (min, max) } else { (0x80, 0xbf) };
+            if c < low || c > high {
+                panic!("UR literal CBOR text is not UTF-8");
+            }
+            j += 1;
+        }
+        i += count + 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dcbor::prelude::*;
+
+    use super::*;
+    use crate::{UR, bytewords};
+
+    fn literal(cbor_data: &[u8]) -> String {
+        format!(
+            "ur:test/{}",
+            bytewords::encode(cbor_data, bytewords::Style::Minimal)
+        )
+    }
+
+    #[test]
+    fn test_ur_macro() {
+        let ur = crate::ur!("ur:test/lsadaoaxjygonesw");
+        assert_eq!(ur, UR::from_ur_string("ur:test/lsadaoaxjygonesw").unwrap());
+        assert_eq!(crate::ur!("UR:TEST/LSADAOAXJYGONESW"), ur);
+    }
+
+    #[test]
+    fn test_check_ur_literal() {
+        // Literals that are rejected, and so fail to compile, are tested in
+        // the module documentation.
+        for cbor in [
+            CBOR::from(vec![1, 2, 3]),
+            CBOR::from("héllo, wörld ✓ 🦀"),
+            CBOR::from(-1_000_000_000_000i64),
+            CBOR::to_byte_string(vec![0; 300]),
+            CBOR::to_tagged_value(40000, vec![CBOR::null(), 1.5.into()]),
+            CBOR::from(Map::new()),
+            CBOR::from(Vec::<CBOR>::new()),
+        ] {
+            let ur_string = literal(&cbor.to_cbor_data());
+            check_ur_literal(&ur_string);
+            assert!(UR::from_ur_string(ur_string).is_ok());
+        }
+        let mut map = Map::new();
+        map.insert(1, vec![CBOR::from("a"), CBOR::from(Map::new())]);
+        map.insert("k", CBOR::to_tagged_value(1, 2));
+        check_ur_literal(&literal(&CBOR::from(map).to_cbor_data()));
+
+        // Well-formed but not dCBOR, so left to the runtime check.
+        check_ur_literal(&literal(&hex::decode("9f0102ff").unwrap()));
+        check_ur_literal(&literal(&hex::decode("7f6161616bff").unwrap()));
+
+        let mut nested = vec![0x81; MAX_NESTING_DEPTH];
+        nested.push(0);
+        check_ur_literal(&literal(&nested));
+    }
+
+    #[test]
+    fn test_rejected_at_runtime() {
+        // The malformed payloads of the rejected literals in the module
+        // documentation are rejected at runtime too, even when permissive.
+        for cbor_data in [
+            "", "830102", "bf01ff", "8301", "ff", "1c", "5f01ff", "5f6161ff",
+            "1f", "62c328", "6180", "63e08080", "a1",
+        ] {
+            let ur_string = literal(&hex::decode(cbor_data).unwrap());
+            assert!(
+                UR::from_ur_string_with_mode(
+                    ur_string,
+                    crate::DecodeMode::Permissive
+                )
+                .is_err(),
+                "{cbor_data:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,115 @@
+import_stdlib!();
+
+use crate::{
+    Error, ParseError, Result, URSegment, URType, bytewords,
+    ur_type::type_error,
+};
+
+/// The components of a UR string, with the payload already decoded from
+/// bytewords.
+pub(crate) struct ParsedUR {
+    pub ur_type: URType,
+    /// The sequence number and count of a multipart UR.
+    pub sequence: Option<(u32, u32)>,
+    /// The byte offset and length of the sequence component, if any.
+    pub sequence_span: Option<(usize, usize)>,
+    pub payload: Vec<u8>,
+}
+
+/// Parses a lowercase UR string, reporting the location of any error.
+pub(crate) fn parse_ur(ur_string: &str) -> Result<ParsedUR> {
+    let fail = |offset, len, segment, reason| {
+        Error::Parse(ParseError::new(offset, len, segment, reason))
+    };
+
+    let Some(without_scheme) = ur_string.strip_prefix("ur:") else {
+        let len = ur_string
+            .find(':')
+            .map_or(ur_string.len().min(3), |i| i + 1);
+        return Err(fail(0, len, URSegment::Scheme, Error::InvalidScheme));
+    };
+    let type_offset = 3;
+
+    let Some((type_str, rest)) = without_scheme.split_once('/') else {
+        return Err(fail(
+            type_offset,
+            without_scheme.len(),
+            URSegment::Type,
+            Error::TypeUnspecified,
+        ));
+    };
+    if let Some((offset, len, err)) = type_error(type_str) {
+        return Err(fail(type_offset + offset, len, URSegment::Type, err));
+    }
+    let ur_type = URType::new(type_str)?;
+    let rest_offset = type_offset + type_str.len() + 1;
+
+    let (sequence, sequence_span, payload_str, payload_offset) =
+        match rest.rsplit_once('/') {
+            None => (None, None, rest, rest_offset),
+            Some((seq_str, payload_str)) => {
+                let sequence = parse_sequence(seq_str).ok_or_else(|| {
+                    fail(
+                        rest_offset,
+                        seq_str.len(),
+                        URSegment::Sequence,
+                        Error::InvalidSequence,
+                    )
+                })?;
+                (
+                    Some(sequence),
+                    Some((rest_offset, seq_str.len())),
+                    payload_str,
+                    rest_offset + seq_str.len() + 1,
+                )
+            }
+        };
+
+    let payload = decode_payload(payload_str, payload_offset)?;
+    Ok(ParsedUR { ur_type, sequence, sequence_span, payload })
+}
+
+fn parse_sequence(seq_str: &str) -> Option<(u32, u32)> {
+    let (seq_num, seq_len) = seq_str.split_once('-')?;
+    if !seq_num.bytes().all(|b| b.is_ascii_digit())
+        || !seq_len.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    Some((seq_num.parse().ok()?, seq_len.parse().ok()?))
+}
+
+/// Decodes a minimal-style bytewords payload. Only when decoding fails is the
+/// payload rescanned to find the offending word.
+fn decode_payload(payload: &str, offset: usize) -> Result<Vec<u8>> {
+    let reason = match ur::bytewords::decode(payload, bytewords::Style::Minimal)
+    {
+        Ok(data) => return Ok(data),
+        Err(err) => err.to_string(),
+    };
+    let fail = |i: usize, len| {
+        Error::Parse(ParseError::new(
+            offset + i,
+            len,
+            URSegment::Payload { word_index: i / 2 },
+            Error::Bytewords(reason.clone()),
+        ))
+    };
+
+    if let Some((i, c)) = payload.char_indices().find(|(_, c)| !c.is_ascii()) {
+        return Err(fail(i, c.len_utf8()));
+    }
+    for (word_index, word) in payload.as_bytes().chunks(2).enumerate() {
+        let i = word_index * 2;
+        if word.len() == 1 {
+            return Err(fail(i, 1));
+        }
+        let word = str::from_utf8(word).unwrap();
+        if bytewords::canonicalize_byteword(word).is_none() {
+            return Err(fail(i, 2));
+        }
+    }
+    // Every word is valid, so the checksum must be at fault.
+    let checksum_start = payload.len().saturating_sub(8);
+    Err(fail(checksum_start, payload.len() - checksum_start))
+}
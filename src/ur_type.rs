@@ -1,20 +1,62 @@
-use crate::{Error, Result, URTypeString};
+import_stdlib!();
 
+use crate::{Error, Result};
+
+/// The type of a UR, such as `seed` or `crypto-psbt`.
+///
+/// A type is a non-empty string of at most [`URType::MAX_LEN`] lowercase
+/// letters, digits and hyphens, which neither begins nor ends with a hyphen
+/// nor has two in a row.
 #[derive(Debug, Clone, PartialEq)]
-pub struct URType(String);
+pub struct URType(Cow<'static, str>);
 
 impl URType {
+    /// The longest UR type accepted, in characters.
+    pub const MAX_LEN: usize = 64;
+
     /// Creates a new URType from the provided type.
     pub fn new(ur_type: impl Into<String>) -> Result<URType> {
         let ur_type = ur_type.into();
-        if !ur_type.is_ur_type() {
-            return Err(Error::InvalidType);
+        match find_problem(ur_type.as_bytes()) {
+            None => Ok(URType(Cow::Owned(ur_type))),
+            Some((_, problem)) => Err(problem.error()),
+        }
+    }
+
+    /// Creates a URType from a static string without allocating.
+    ///
+    /// Panics if the type is invalid, which in a const context, such as the
+    /// [`ur_type!`](crate::ur_type) macro, is a compile-time error.
+    pub const fn from_static(ur_type: &'static str) -> URType {
+        if find_problem(ur_type.as_bytes()).is_some() {
+            panic!("invalid UR type");
         }
-        Ok(URType(ur_type))
+        URType(Cow::Borrowed(ur_type))
     }
 
     /// Returns the String representation of the URType.
     pub fn string(&self) -> &str { &self.0 }
+
+    /// Returns `true` if this type is marked sensitive in the global UR type
+    /// registry, as the [`SENSITIVE_UR_TYPES`](crate::SENSITIVE_UR_TYPES)
+    /// are by default.
+    ///
+    /// This reads the registry, so it must not be called from within
+    /// [`with_ur_types_mut`](crate::with_ur_types_mut).
+    pub fn is_sensitive(&self) -> bool {
+        crate::with_ur_types(|registry| registry.is_sensitive(self.string()))
+    }
+
+    /// Returns `true` if this type is sensitive, or if that cannot be checked
+    /// without waiting because the registry is being written. Formatting uses
+    /// this, so that it can be done from within
+    /// [`with_ur_types_mut`](crate::with_ur_types_mut).
+    pub(crate) fn should_redact(&self) -> bool {
+        crate::ur_type_registry::try_with_ur_types(|registry| {
+            registry.is_sensitive(self.string())
+        })
+        .unwrap_or(true)
+    }
 }
 
 impl TryFrom<String> for URType {
@@ -28,3 +70,148 @@ impl TryFrom<&str> for URType {
 
     fn try_from(value: &str) -> Result<Self> { URType::new(value) }
 }
+
+/// Creates a [`URType`] from a string literal, which is validated at compile
+/// time, without allocating.
+///
+/// ```
+/// use bc_ur::{URType, ur_type};
+///
+/// const SEED: URType = ur_type!("seed");
+/// assert_eq!(SEED.string(), "seed");
+/// ```
+///
+/// An invalid type does not compile:
+///
+/// ```compile_fail
+/// let ur_type = bc_ur::ur_type!("-seed");
+/// ```
+#[macro_export]
+macro_rules! ur_type {
+    ($ur_type:literal) => {
+        const { $crate::URType::from_static($ur_type) }
+    };
+}
+
+/// The ways in which a string can fail to be a UR type.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Problem {
+    Empty,
+    InvalidChar,
+    Hyphen,
+    TooLong,
+}
+
+impl Problem {
+    fn error(self) -> Error {
+        match self {
+            Problem::Empty => Error::TypeUnspecified,
+            Problem::InvalidChar => Error::InvalidType,
+            Problem::Hyphen => Error::InvalidTypeHyphen,
+            Problem::TooLong => Error::TypeTooLong(URType::MAX_LEN),
+        }
+    }
+}
+
+/// Returns the byte offset and length of the first problem that keeps
+/// `ur_type` from being a valid UR type, and the error describing it.
+pub(crate) fn type_error(ur_type: &str) -> Option<(usize, usize, Error)> {
+    let (offset, problem) = find_problem(ur_type.as_bytes())?;
+    let len = match problem {
+        Problem::Empty => 0,
+        Problem::InvalidChar => {
+            ur_type[offset..].chars().next().map_or(1, char::len_utf8)
+        }
+        Problem::Hyphen => 1,
+        Problem::TooLong => ur_type.len() - offset,
+    };
+    Some((offset, len, problem.error()))
+}
+
+/// Returns the byte offset of the first problem that keeps `bytes` from
+/// being a valid UR type, and what the problem is.
+pub(crate) const fn find_problem(bytes: &[u8]) -> Option<(usize, Problem)> {
+    find_problem_in_case(bytes, false)
+}
+
+/// As [`find_problem`], but accepting uppercase letters too if `any_case`,
+/// as in the uppercase form of a UR.
+pub(crate) const fn find_problem_in_case(
+    bytes: &[u8],
+    any_case: bool,
+) -> Option<(usize, Problem)> {
+    if bytes.is_empty() {
+        return Some((0, Problem::Empty));
+    }
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = if any_case {
+            bytes[i].to_ascii_lowercase()
+        } else {
+            bytes[i]
+        };
+        if !(b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-') {
+            return Some((i, Problem::InvalidChar));
+        }
+        if b == b'-' && (i == 0 || i == bytes.len() - 1 || bytes[i - 1] == b'-')
+        {
+            return Some((i, Problem::Hyphen));
+        }
+        i += 1;
+    }
+    if bytes.len() > URType::MAX_LEN {
+        return Some((URType::MAX_LEN, Problem::TooLong));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid() {
+        for ur_type in ["seed", "crypto-psbt", "a", "x-1-2", "0"] {
+            assert_eq!(URType::new(ur_type).unwrap().string(), ur_type);
+        }
+        let longest = "a".repeat(URType::MAX_LEN);
+        assert!(URType::new(longest).is_ok());
+    }
+
+    #[test]
+    fn test_invalid() {
+        let too_long = "a".repeat(URType::MAX_LEN + 1);
+        for (ur_type, offset, len) in [
+            ("", 0, 0),
+            ("Seed", 0, 1),
+            ("se_ed", 2, 1),
+            ("séed", 1, 2),
+            ("-", 0, 1),
+            ("-seed", 0, 1),
+            ("seed-", 4, 1),
+            ("se--ed", 3, 1),
+            (too_long.as_str(), URType::MAX_LEN, 1),
+        ] {
+            let (o, l, err) = type_error(ur_type).unwrap();
+            assert_eq!((o, l), (offset, len), "{ur_type:?}");
+            assert_eq!(
+                URType::new(ur_type).unwrap_err().to_string(),
+                err.to_string()
+            );
+        }
+        assert!(matches!(URType::new(""), Err(Error::TypeUnspecified)));
+        assert!(matches!(URType::new("a b"), Err(Error::InvalidType)));
+        assert!(matches!(URType::new("a-"), Err(Error::InvalidTypeHyphen)));
+        assert!(matches!(
+            URType::new(too_long),
+            Err(Error::TypeTooLong(URType::MAX_LEN))
+        ));
+    }
+
+    #[test]
+    fn test_ur_type_macro() {
+        const SEED: URType = ur_type!("seed");
+        assert_eq!(SEED, URType::new("seed").unwrap());
+        assert_eq!(ur_type!("crypto-psbt").string(), "crypto-psbt");
+    }
+}
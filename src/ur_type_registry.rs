@@ -0,0 +1,457 @@
+import_stdlib!();
+
+use dcbor::prelude::*;
+
+use crate::{Error, Result, URType};
+
+/// UR types and their CBOR tags from [BCR-2020-006: Registry of Uniform
+/// Resource (UR)
+/// Types](https://github.com/BlockchainCommons/Research/blob/master/papers/bcr-2020-006-urtypes.md).
+///
+/// Each entry is `(type, tag, deprecated)`. The `crypto-` prefixed types are
+/// the legacy (V1) forms that have been superseded by unprefixed types in the
+/// 40000 tag range.
+pub const KNOWN_UR_TYPES: &[(&str, TagValue, bool)] = &[
+    ("envelope", 200, false),
+    ("leaf", 201, false),
+    ("json", 262, false),
+    ("crypto-seed", 300, true),
+    ("crypto-bip39", 301, true),
+    ("crypto-hdkey", 303, true),
+    ("crypto-keypath", 304, true),
+    ("crypto-coin-info", 305, true),
+    ("crypto-eckey", 306, true),
+    ("crypto-address", 307, true),
+    ("crypto-output", 308, true),
+    ("crypto-sskr", 309, true),
+    ("crypto-psbt", 310, true),
+    ("crypto-account", 311, true),
+    ("known-value", 40000, false),
+    ("digest", 40001, false),
+    ("encrypted", 40002, false),
+    ("compressed", 40003, false),
+    ("request", 40004, false),
+    ("response", 40005, false),
+    ("function", 40006, false),
+    ("parameter", 40007, false),
+    ("placeholder", 40008, false),
+    ("replacement", 40009, false),
+    ("agreement-private-key", 40010, false),
+    ("agreement-public-key", 40011, false),
+    ("arid", 40012, false),
+    ("crypto-prvkeys", 40013, false),
+    ("nonce", 40014, false),
+    ("password", 40015, false),
+    ("crypto-prvkey-base", 40016, false),
+    ("crypto-pubkeys", 40017, false),
+    ("salt", 40018, false),
+    ("crypto-sealed", 40019, false),
+    ("signature", 40020, false),
+    ("signing-private-key", 40021, false),
+    ("signing-public-key", 40022, false),
+    ("crypto-key", 40023, false),
+    ("xid", 40024, false),
+    ("reference", 40025, false),
+    ("event", 40026, false),
+    ("encrypted-key", 40027, false),
+    ("seed", 40300, false),
+    ("hdkey", 40303, false),
+    ("keypath", 40304, false),
+    ("coin-info", 40305, false),
+    ("eckey", 40306, false),
+    ("address", 40307, false),
+    ("output-descriptor", 40308, false),
+    ("sskr", 40309, false),
+    ("psbt", 40310, false),
+    ("account-descriptor", 40311, false),
+];
+
+/// UR types whose payloads are, or may be, secret: seeds, shares, private
+/// and symmetric keys, and passwords. The `hdkey` and `eckey` types are
+/// included because they may hold private keys.
+///
+/// The `Debug` output of a [`UR`](crate::UR) of a sensitive type is redacted,
+/// as is the `Display` output of [`UR::redacted`](crate::UR::redacted). The
+/// `Display` output of the UR itself is the full UR string.
+pub const SENSITIVE_UR_TYPES: &[&str] = &[
+    "seed",
+    "crypto-seed",
+    "crypto-bip39",
+    "sskr",
+    "crypto-sskr",
+    "hdkey",
+    "crypto-hdkey",
+    "eckey",
+    "crypto-eckey",
+    "crypto-prvkeys",
+    "crypto-prvkey-base",
+    "agreement-private-key",
+    "signing-private-key",
+    "crypto-key",
+    "password",
+];
+
+/// A UR type and the CBOR tag it corresponds to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct URTypeEntry {
+    ur_type: URType,
+    tag: TagValue,
+    deprecated: bool,
+}
+
+impl URTypeEntry {
+    pub fn ur_type(&self) -> &URType { &self.ur_type }
+
+    pub fn tag(&self) -> TagValue { self.tag }
+
+    /// Returns `true` if this is a legacy name that new encoders should not
+    /// produce.
+    pub fn is_deprecated(&self) -> bool { self.deprecated }
+}
+
+/// A bidirectional mapping between UR types and CBOR tags.
+///
+/// Each UR type maps to exactly one tag. When several types share a tag, the
+/// non-deprecated one is preferred for lookups by tag.
+#[derive(Debug, Clone, Default)]
+pub struct URTypeRegistry {
+    by_type: HashMap<String, URTypeEntry>,
+    by_tag: HashMap<TagValue, URTypeEntry>,
+    sensitive: HashSet<String>,
+}
+
+impl URTypeRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self { Self::default() }
+
+    /// Creates a registry pre-populated with [`KNOWN_UR_TYPES`], with the
+    /// [`SENSITIVE_UR_TYPES`] marked sensitive.
+    pub fn with_known_types() -> Self {
+        let mut registry = Self::new();
+        for &(ur_type, tag, deprecated) in KNOWN_UR_TYPES {
+            registry.insert(ur_type, tag, deprecated).unwrap();
+        }
+        for &ur_type in SENSITIVE_UR_TYPES {
+            registry.mark_sensitive(ur_type).unwrap();
+        }
+        registry
+    }
+
+    /// Registers a UR type for the given CBOR tag.
+    ///
+    /// Registering an identical mapping again is not an error, but mapping a
+    /// type to a second tag, or a tag to a second non-deprecated type, is.
+    pub fn register(
+        &mut self,
+        ur_type: impl TryInto<URType, Error = Error>,
+        tag: TagValue,
+    ) -> Result<()> {
+        self.insert(ur_type, tag, false)
+    }
+
+    /// Registers a legacy UR type for the given CBOR tag.
+    pub fn register_deprecated(
+        &mut self,
+        ur_type: impl TryInto<URType, Error = Error>,
+        tag: TagValue,
+    ) -> Result<()> {
+        self.insert(ur_type, tag, true)
+    }
+
+    fn insert(
+        &mut self,
+        ur_type: impl TryInto<URType, Error = Error>,
+        tag: TagValue,
+        deprecated: bool,
+    ) -> Result<()> {
+        let ur_type = ur_type.try_into()?;
+        let name = ur_type.string().to_string();
+        if let Some(existing) = self.by_type.get(&name)
+            && existing.tag != tag
+        {
+            return Err(Error::TypeAlreadyRegistered(name, existing.tag));
+        }
+        let entry = URTypeEntry { ur_type, tag, deprecated };
+        match self.by_tag.get(&tag) {
+            Some(existing) if existing.ur_type != entry.ur_type => {
+                if !existing.deprecated && !deprecated {
+                    return Err(Error::TagAlreadyRegistered(
+                        tag,
+                        existing.ur_type.string().to_string(),
+                    ));
+                }
+                if existing.deprecated && !deprecated {
+                    self.by_tag.insert(tag, entry.clone());
+                }
+            }
+            _ => {
+                self.by_tag.insert(tag, entry.clone());
+            }
+        }
+        self.by_type.insert(name, entry);
+        Ok(())
+    }
+
+    /// Marks a UR type as carrying secrets. The type need not be registered
+    /// for a tag.
+    pub fn mark_sensitive(
+        &mut self,
+        ur_type: impl TryInto<URType, Error = Error>,
+    ) -> Result<()> {
+        let ur_type = ur_type.try_into()?;
+        self.sensitive.insert(ur_type.string().to_string());
+        Ok(())
+    }
+
+    /// Returns `true` if the UR type has been marked sensitive.
+    pub fn is_sensitive(&self, ur_type: &str) -> bool {
+        self.sensitive.contains(ur_type)
+    }
+
+    /// Returns the entry for the given UR type, if registered.
+    pub fn entry_for_type(&self, ur_type: &str) -> Option<&URTypeEntry> {
+        self.by_type.get(ur_type)
+    }
+
+    /// Returns the preferred entry for the given CBOR tag, if registered.
+    pub fn entry_for_tag(&self, tag: TagValue) -> Option<&URTypeEntry> {
+        self.by_tag.get(&tag)
+    }
+
+    /// Returns the CBOR tag for the given UR type, if registered.
+    pub fn tag_for_type(&self, ur_type: &str) -> Option<TagValue> {
+        self.entry_for_type(ur_type).map(|entry| entry.tag)
+    }
+
+    /// Returns the preferred UR type for the given CBOR tag, if registered.
+    pub fn type_for_tag(&self, tag: TagValue) -> Option<&URType> {
+        self.entry_for_tag(tag).map(|entry| &entry.ur_type)
+    }
+
+    /// Returns all registered entries, in no particular order.
+    pub fn entries(&self) -> impl Iterator<Item = &URTypeEntry> {
+        self.by_type.values()
+    }
+}
+
+static GLOBAL_UR_TYPES: LazyLock<RwLock<URTypeRegistry>> =
+    LazyLock::new(|| RwLock::new(URTypeRegistry::with_known_types()));
+
+/// Calls `f` with read access to the global UR type registry, which starts out
+/// populated with [`KNOWN_UR_TYPES`].
+///
+/// A panic in an earlier call to [`with_ur_types_mut`] does not poison the
+/// registry.
+pub fn with_ur_types<R>(f: impl FnOnce(&URTypeRegistry) -> R) -> R {
+    #[cfg(feature = "std")]
+    let registry = GLOBAL_UR_TYPES
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    #[cfg(not(feature = "std"))]
+    let registry = GLOBAL_UR_TYPES.read();
+    f(&registry)
+}
+
+/// Calls `f` with read access to the global UR type registry, or returns
+/// `None` without waiting if the registry is being written, as from within
+/// [`with_ur_types_mut`].
+pub(crate) fn try_with_ur_types<R>(
+    f: impl FnOnce(&URTypeRegistry) -> R,
+) -> Option<R> {
+    #[cfg(feature = "std")]
+    let registry = match GLOBAL_UR_TYPES.try_read() {
+        Ok(registry) => registry,
+        Err(std::sync::TryLockError::Poisoned(err)) => err.into_inner(),
+        Err(std::sync::TryLockError::WouldBlock) => return None,
+    };
+    #[cfg(not(feature = "std"))]
+    let registry = GLOBAL_UR_TYPES.try_read()?;
+    Some(f(&registry))
+}
+
+/// Calls `f` with write access to the global UR type registry.
+///
+/// `f` must not read the global registry, which would deadlock; in
+/// particular, it must not call [`URType::is_sensitive`]. Formatting a UR
+/// within `f` does not deadlock, but redacts it whatever its type.
+pub fn with_ur_types_mut<R>(f: impl FnOnce(&mut URTypeRegistry) -> R) -> R {
+    #[cfg(feature = "std")]
+    let mut registry = GLOBAL_UR_TYPES
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    #[cfg(not(feature = "std"))]
+    let mut registry = GLOBAL_UR_TYPES.write();
+    f(&mut registry)
+}
+
+/// Registers a UR type for the given CBOR tag in the global registry.
+pub fn register_ur_type(
+    ur_type: impl TryInto<URType, Error = Error>,
+    tag: TagValue,
+) -> Result<()> {
+    with_ur_types_mut(|registry| registry.register(ur_type, tag))
+}
+
+/// Marks a UR type as carrying secrets in the global registry, so that the
+/// `Debug` output of URs of that type is redacted.
+pub fn mark_ur_type_sensitive(
+    ur_type: impl TryInto<URType, Error = Error>,
+) -> Result<()> {
+    with_ur_types_mut(|registry| registry.mark_sensitive(ur_type))
+}
+
+/// Returns the UR type for a CBOR tag: the tag's own name if it has one,
+/// otherwise the type registered for its value in the global registry.
+pub(crate) fn ur_type_for_tag(tag: &Tag) -> Option<String> {
+    tag.name().or_else(|| {
+        with_ur_types(|registry| {
+            registry
+                .type_for_tag(tag.value())
+                .map(|t| t.string().to_string())
+        })
+    })
+}
+
+/// A tags store that names each CBOR tag as the global tags store does or,
+/// failing that, by the UR type registered for it.
+pub(crate) struct RegistryTags<'a>(pub(crate) &'a URTypeRegistry);
+
+impl RegistryTags<'_> {
+    fn registered_tag(&self, value: TagValue) -> Option<Tag> {
+        self.0
+            .type_for_tag(value)
+            .map(|ur_type| Tag::new(value, ur_type.string()))
+    }
+}
+
+impl TagsStoreTrait for RegistryTags<'_> {
+    fn assigned_name_for_tag(&self, tag: &Tag) -> Option<String> {
+        self.tag_for_value(tag.value()).and_then(|tag| tag.name())
+    }
+
+    fn name_for_tag(&self, tag: &Tag) -> String {
+        self.assigned_name_for_tag(tag)
+            .unwrap_or_else(|| tag.value().to_string())
+    }
+
+    fn tag_for_value(&self, value: TagValue) -> Option<Tag> {
+        dcbor::with_tags!(|tags: &TagsStore| tags.tag_for_value(value))
+            .or_else(|| self.registered_tag(value))
+    }
+
+    fn tag_for_name(&self, name: &str) -> Option<Tag> {
+        dcbor::with_tags!(|tags: &TagsStore| tags.tag_for_name(name)).or_else(
+            || {
+                self.0
+                    .tag_for_type(name)
+                    .and_then(|t| self.registered_tag(t))
+            },
+        )
+    }
+
+    fn name_for_value(&self, value: TagValue) -> String {
+        self.name_for_tag(&Tag::with_value(value))
+    }
+
+    // The global store's summarizers cannot be borrowed past its lock, and
+    // the registry has none.
+    fn summarizer(&self, _tag: TagValue) -> Option<&CBORSummarizer> { None }
+}
+
+/// Returns the UR type for the first CBOR tag of `T`.
+pub(crate) fn ur_type_for<T: CBORTagged>() -> Result<String> {
+    let tag = &T::cbor_tags()[0];
+    ur_type_for_tag(tag).ok_or(Error::UnknownTag(tag.value()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_types() {
+        let registry = URTypeRegistry::with_known_types();
+        assert_eq!(registry.tag_for_type("seed"), Some(40300));
+        assert_eq!(registry.tag_for_type("crypto-psbt"), Some(310));
+        assert_eq!(registry.type_for_tag(40310).unwrap().string(), "psbt");
+        assert!(
+            registry
+                .entry_for_type("crypto-seed")
+                .unwrap()
+                .is_deprecated()
+        );
+        assert!(!registry.entry_for_type("seed").unwrap().is_deprecated());
+        assert_eq!(registry.tag_for_type("bytes"), None);
+        assert!(registry.is_sensitive("seed"));
+        assert!(!registry.is_sensitive("psbt"));
+    }
+
+    #[test]
+    fn test_register() {
+        let mut registry = URTypeRegistry::new();
+        registry.register("my-type", 90000).unwrap();
+        registry.register("my-type", 90000).unwrap();
+        assert!(matches!(
+            registry.register("my-type", 90001),
+            Err(Error::TypeAlreadyRegistered(_, 90000))
+        ));
+        assert!(matches!(
+            registry.register("other-type", 90000),
+            Err(Error::TagAlreadyRegistered(90000, _))
+        ));
+        assert!(matches!(
+            registry.register("Bad Type", 90002),
+            Err(Error::InvalidType)
+        ));
+    }
+
+    #[test]
+    fn test_deprecated_alias() {
+        let mut registry = URTypeRegistry::new();
+        registry.register_deprecated("old-type", 90000).unwrap();
+        registry.register("new-type", 90000).unwrap();
+        assert_eq!(registry.type_for_tag(90000).unwrap().string(), "new-type");
+        assert_eq!(registry.tag_for_type("old-type"), Some(90000));
+    }
+
+    #[test]
+    fn test_mark_sensitive() {
+        let mut registry = URTypeRegistry::new();
+        assert!(!registry.is_sensitive("seed"));
+        registry.mark_sensitive("my-secret").unwrap();
+        assert!(registry.is_sensitive("my-secret"));
+        assert!(registry.tag_for_type("my-secret").is_none());
+        assert!(matches!(
+            registry.mark_sensitive("Bad Type"),
+            Err(Error::InvalidType)
+        ));
+    }
+
+    #[test]
+    fn test_global_registry() {
+        // The global registry is shared by every test, so this uses a type
+        // and tag that no other test touches.
+        const UR_TYPE: &str = "test-global-registry";
+        const TAG: TagValue = 4_000_000_027;
+
+        register_ur_type(UR_TYPE, TAG).unwrap();
+        register_ur_type(UR_TYPE, TAG).unwrap();
+        assert_eq!(
+            with_ur_types(|registry| registry.tag_for_type(UR_TYPE)),
+            Some(TAG)
+        );
+        assert_eq!(
+            ur_type_for_tag(&Tag::with_value(TAG)).as_deref(),
+            Some(UR_TYPE)
+        );
+        assert!(matches!(
+            register_ur_type("test-global-registry-other", TAG),
+            Err(Error::TagAlreadyRegistered(TAG, _))
+        ));
+
+        assert!(!URType::new(UR_TYPE).unwrap().is_sensitive());
+        mark_ur_type_sensitive(UR_TYPE).unwrap();
+        assert!(with_ur_types(|registry| registry.is_sensitive(UR_TYPE)));
+        assert!(URType::new(UR_TYPE).unwrap().is_sensitive());
+    }
+}
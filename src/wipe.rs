@@ -0,0 +1,56 @@
+import_stdlib!();
+
+use core::ops::{Deref, DerefMut};
+
+/// A buffer that may hold a sensitive payload, such as a seed, and is cleared
+/// before it is freed when the `zeroize` feature is enabled.
+pub(crate) trait Wipe {
+    fn wipe(&mut self);
+}
+
+impl Wipe for Vec<u8> {
+    fn wipe(&mut self) {
+        #[cfg(feature = "zeroize")]
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
+impl Wipe for String {
+    fn wipe(&mut self) {
+        #[cfg(feature = "zeroize")]
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
+/// Wipes the buffer it holds when dropped, including on early returns.
+pub(crate) struct Wiped<T: Wipe>(pub T);
+
+impl<T: Wipe> Deref for Wiped<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T { &self.0 }
+}
+
+impl<T: Wipe> DerefMut for Wiped<T> {
+    fn deref_mut(&mut self) -> &mut T { &mut self.0 }
+}
+
+impl<T: Wipe> Drop for Wiped<T> {
+    fn drop(&mut self) { self.0.wipe(); }
+}
+
+#[cfg(all(test, feature = "zeroize"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wipe() {
+        let mut data = vec![0xaa; 16];
+        data.wipe();
+        assert!(data.is_empty());
+
+        let mut s = Wiped(String::from("ur:seed/secret"));
+        s.wipe();
+        assert!(s.is_empty());
+    }
+}
@@ -0,0 +1,85 @@
+import_stdlib!();
+
+use sha2::{Digest, Sha256};
+
+use crate::random_sampler::RandomSampler;
+
+/// The Xoshiro256** pseudorandom number generator, seeded as specified by
+/// BCR-2020-005 so that fountain encoders and decoders choose the same
+/// fragments for each part.
+pub(crate) struct Xoshiro256 {
+    s: [u64; 4],
+}
+
+impl Xoshiro256 {
+    /// Creates a generator seeded with the SHA-256 digest of `data`.
+    pub fn from_data(data: &[u8]) -> Self {
+        Self::from_digest(Sha256::digest(data).into())
+    }
+
+    /// Creates a generator whose state is the digest read as four big-endian
+    /// words.
+    pub fn from_digest(digest: [u8; 32]) -> Self {
+        let mut s = [0u64; 4];
+        for (word, chunk) in s.iter_mut().zip(digest.chunks_exact(8)) {
+            *word = u64::from_be_bytes(chunk.try_into().unwrap());
+        }
+        Self { s }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let result = self.s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = self.s[1] << 17;
+
+        self.s[2] ^= self.s[0];
+        self.s[3] ^= self.s[1];
+        self.s[1] ^= self.s[2];
+        self.s[0] ^= self.s[3];
+
+        self.s[2] ^= t;
+        self.s[3] = self.s[3].rotate_left(45);
+
+        result
+    }
+
+    /// Returns a value in `[0, 1)`.
+    pub fn next_double(&mut self) -> f64 {
+        self.next_u64() as f64 / (u64::MAX as f64 + 1.0)
+    }
+
+    /// Returns a value in `[low, high]`.
+    pub fn next_int(&mut self, low: u64, high: u64) -> u64 {
+        (self.next_double() * (high - low + 1) as f64) as u64 + low
+    }
+
+    pub fn next_byte(&mut self) -> u8 { self.next_int(0, 255) as u8 }
+
+    pub fn next_data(&mut self, count: usize) -> Vec<u8> {
+        (0..count).map(|_| self.next_byte()).collect()
+    }
+
+    /// Returns the items in an order determined by the generator.
+    #[cfg(test)]
+    pub fn shuffled<T>(&mut self, mut items: Vec<T>) -> Vec<T> {
+        let mut shuffled = Vec::with_capacity(items.len());
+        while !items.is_empty() {
+            let index = self.next_int(0, (items.len() - 1) as u64) as usize;
+            shuffled.push(items.remove(index));
+        }
+        shuffled
+    }
+
+    /// Chooses how many of `seq_len` fragments to mix into a part, favoring
+    /// smaller degrees.
+    pub fn choose_degree(&mut self, seq_len: usize) -> usize {
+        let weights = (1..=seq_len).map(|i| 1.0 / i as f64).collect();
+        RandomSampler::new(weights).next(self) + 1
+    }
+}
+
+/// Returns `len` pseudorandom bytes seeded from `seed`, as used by the URKit
+/// test vectors.
+#[cfg(test)]
+pub(crate) fn make_message(seed: &str, len: usize) -> Vec<u8> {
+    Xoshiro256::from_data(seed.as_bytes()).next_data(len)
+}